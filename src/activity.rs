@@ -0,0 +1,297 @@
+use crate::equipment;
+use crate::hazards;
+use crate::lighting;
+use crate::map::Game;
+use crate::objects;
+use crate::pathfinding;
+use crate::regeneration;
+use crate::terrain::Terrain;
+use crate::world::{Entity, World};
+use tcod::colors::*;
+use tcod::map::Map as FovMap;
+
+/// What the player can set out to do by themselves, a step every turn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Task {
+    /// head for the nearest place that hasn't been explored yet
+    Explore,
+    /// Keep going in one direction until the way ahead is blocked, or
+    /// something interesting turns up. `open` is how many ways on there were
+    /// from the last tile, to notice corridors branching off.
+    Run {
+        dx: i32,
+        dy: i32,
+        open: Option<usize>,
+    },
+    /// walk to a tile the player has already explored
+    Travel { to: (i32, i32) },
+    /// wait around until fully healed
+    Rest,
+}
+
+/// A task the player is busy with, along with what they knew when they last
+/// looked around, so they can stop as soon as something new comes up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Activity {
+    pub task: Task,
+    hp: i32,
+    items_seen: Vec<Entity>,
+}
+
+impl Activity {
+    /// Set out on a task, unless there's a monster in view to deal with first.
+    pub fn start(task: Task, fov: &FovMap, game: &mut Game, world: &World) -> Option<Activity> {
+        if let Some(&monster) = monsters_in_view(fov, game, world).first() {
+            game.messages.add(
+                format!("Not with the {} in view!", world.name(monster)),
+                WHITE,
+            );
+            return None;
+        }
+        Some(Activity {
+            task,
+            hp: world.fighters.get(world.player).map_or(0, |f| f.hp),
+            items_seen: items_in_view(fov, game, world),
+        })
+    }
+
+    /// Look around for anything the player would want to stop for: a monster
+    /// turning up, getting hurt, or something new lying around.
+    pub fn interrupted(&mut self, fov: &FovMap, game: &mut Game, world: &World) -> bool {
+        if let Some(&monster) = monsters_in_view(fov, game, world).first() {
+            game.messages
+                .add(format!("You see a {}.", world.name(monster)), WHITE);
+            return true;
+        }
+        let hp = world.fighters.get(world.player).map_or(0, |f| f.hp);
+        if hp < self.hp {
+            game.messages.add("You stop, hurt.", WHITE);
+            return true;
+        }
+        self.hp = hp;
+        let items = items_in_view(fov, game, world);
+        if let Some(&item) = items.iter().find(|item| !self.items_seen.contains(item)) {
+            game.messages
+                .add(format!("You spot a {}.", world.name(item)), WHITE);
+            return true;
+        }
+        self.items_seen.extend(items);
+        false
+    }
+
+    /// Take the next step of the task. Returns `false` if there was nothing
+    /// left to do, so no turn was taken.
+    pub fn step(&mut self, game: &mut Game, world: &mut World) -> bool {
+        match self.task {
+            Task::Explore => explore_step(game, world),
+            Task::Run {
+                dx,
+                dy,
+                ref mut open,
+            } => run_step(dx, dy, open, game, world),
+            Task::Travel { to } => travel_step(to, game, world),
+            Task::Rest => rest_step(game, world),
+        }
+    }
+}
+
+fn monsters_in_view(fov: &FovMap, game: &Game, world: &World) -> Vec<Entity> {
+    world
+        .ais
+        .entities()
+        .into_iter()
+        .filter(|&monster| {
+            let (x, y) = world.pos(monster);
+            lighting::is_visible(x, y, fov, game)
+        })
+        .collect()
+}
+
+/// the things worth picking up (or eating) that the player can see right now
+fn items_in_view(fov: &FovMap, game: &Game, world: &World) -> Vec<Entity> {
+    let on_map = world
+        .items
+        .entities()
+        .into_iter()
+        .filter(|item| !world.inventory.contains(item));
+    world
+        .corpses
+        .entities()
+        .into_iter()
+        .chain(on_map)
+        .filter(|&item| {
+            let (x, y) = world.pos(item);
+            lighting::is_visible(x, y, fov, game)
+        })
+        .collect()
+}
+
+/// What it costs the player to walk onto a tile, going by what they remember
+/// of it. Unexplored tiles, lava and traps they know about are off limits.
+pub fn known_cost(x: i32, y: i32, game: &Game, world: &World) -> Option<i32> {
+    let tile = game.map.get(x, y).filter(|tile| tile.explored)?;
+    let info = tile.memory.terrain.info();
+    let passable = !info.blocks || tile.memory.terrain == Terrain::Door;
+    let trapped = hazards::trap_at(x, y, world)
+        .filter(|&trap| !hazards::is_hidden(trap, world))
+        .is_some();
+    if passable && !info.hazardous && !trapped {
+        Some(info.move_cost)
+    } else {
+        None
+    }
+}
+
+/// an explored tile the player can reach, right next to one they haven't seen
+fn is_frontier(x: i32, y: i32, game: &Game, world: &World) -> bool {
+    known_cost(x, y, game, world).is_some()
+        && game
+            .map
+            .neighbours(x, y)
+            .any(|(nx, ny)| !game.map[(nx, ny)].explored)
+}
+
+fn explore_step(game: &mut Game, world: &mut World) -> bool {
+    let (x, y) = world.pos(world.player);
+    let path = pathfinding::find_nearest(
+        game.map.width(),
+        game.map.height(),
+        (x, y),
+        |x, y| known_cost(x, y, game, world),
+        |x, y| is_frontier(x, y, game, world),
+    );
+    match path.and_then(|path| path.first().copied()) {
+        Some((next_x, next_y)) => {
+            objects::player_move_or_attack(next_x - x, next_y - y, game, world);
+            true
+        }
+        None => {
+            game.messages.add("There's nowhere left to explore.", WHITE);
+            false
+        }
+    }
+}
+
+/// how many of the tiles around this one the player could walk onto
+fn ways_on(x: i32, y: i32, game: &Game, world: &World) -> usize {
+    game.map
+        .neighbours(x, y)
+        .filter(|&(nx, ny)| known_cost(nx, ny, game, world).is_some())
+        .count()
+}
+
+fn run_step(
+    dx: i32,
+    dy: i32,
+    open: &mut Option<usize>,
+    game: &mut Game,
+    world: &mut World,
+) -> bool {
+    let (x, y) = world.pos(world.player);
+    let ways = ways_on(x, y, game, world);
+    if let Some(previous) = *open {
+        // stop for anything lying here, and where a corridor branches off or
+        // opens up into a room
+        if world.at(x, y).iter().any(|&entity| {
+            world.corpses.contains(entity)
+                || (world.items.contains(entity) && !world.inventory.contains(&entity))
+        }) {
+            return false;
+        }
+        if previous <= 2 && ways != previous {
+            return false;
+        }
+    }
+    let blocked = world
+        .at(x + dx, y + dy)
+        .iter()
+        .any(|&entity| world.objects[entity].blocks);
+    if blocked || known_cost(x + dx, y + dy, game, world).is_none() {
+        return false;
+    }
+    *open = Some(ways);
+    objects::player_move_or_attack(dx, dy, game, world);
+    true
+}
+
+fn travel_step(to: (i32, i32), game: &mut Game, world: &mut World) -> bool {
+    let (x, y) = world.pos(world.player);
+    if (x, y) == to {
+        return false;
+    }
+    let path = pathfinding::find_path(
+        game.map.width(),
+        game.map.height(),
+        (x, y),
+        to,
+        i32::MAX,
+        |x, y| known_cost(x, y, game, world),
+    );
+    match path.and_then(|path| path.first().copied()) {
+        Some((next_x, next_y)) => {
+            objects::player_move_or_attack(next_x - x, next_y - y, game, world);
+            true
+        }
+        None => {
+            game.messages.add("You don't know a way there.", WHITE);
+            false
+        }
+    }
+}
+
+fn rest_step(game: &mut Game, world: &World) -> bool {
+    let hp = world.fighters.get(world.player).map_or(0, |f| f.hp);
+    if hp >= equipment::max_hp(world.player, world) {
+        game.messages.add("You feel rested.", WHITE);
+        false
+    } else if !regeneration::can_regenerate(world.player, world) {
+        game.messages.add("You are too hungry to rest.", WHITE);
+        false
+    } else {
+        // just let the turn pass
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::{self, Item};
+    use crate::objects::Object;
+
+    /// a room the player already knows every tile of, with them standing in it
+    fn setup() -> (Game, World) {
+        let mut game = Game::for_tests(20, 10);
+        for y in 0..10 {
+            for x in 0..20 {
+                game.map[(x, y)].explored = true;
+            }
+        }
+        let world = World::new(20, 10, Object::new(2, 5, '@', "player", WHITE, false));
+        (game, world)
+    }
+
+    fn run_east(game: &mut Game, world: &mut World) {
+        let mut open = None;
+        let mut steps = 0;
+        while run_step(1, 0, &mut open, game, world) {
+            steps += 1;
+            assert!(steps < 100, "the run never stopped");
+        }
+    }
+
+    #[test]
+    fn run_stops_at_the_wall() {
+        let (mut game, mut world) = setup();
+        run_east(&mut game, &mut world);
+        assert_eq!(world.pos(world.player), (18, 5));
+    }
+
+    #[test]
+    fn run_stops_on_an_item() {
+        let (mut game, mut world) = setup();
+        items::spawn_item(Item::Ration, 9, 5, &mut world);
+        run_east(&mut game, &mut world);
+        assert_eq!(world.pos(world.player), (9, 5));
+    }
+}
@@ -0,0 +1,444 @@
+use crate::corpses;
+use crate::global;
+use crate::map;
+use crate::objects;
+use crate::sight;
+use crate::world::{Entity, World};
+use rand::Rng;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ai {
+    Basic,
+    /// keeps its distance and shoots from up to `range` tiles away
+    Ranged {
+        range: i32,
+    },
+    /// hunts together: once one member spots the player the others are alerted
+    Pack {
+        alerted: bool,
+    },
+    /// roams around at random until the player comes into view
+    Wandering,
+    /// raises nearby corpses to fight for it, and keeps out of melee
+    Necromancer,
+    /// runs from the player until healthy enough to resume `previous_ai`
+    Fleeing {
+        previous_ai: Box<Ai>,
+    },
+}
+
+/// What a monster knows of the player: whether it's paying attention at all,
+/// and where it last saw (or heard) them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Awareness {
+    pub awake: bool,
+    pub last_known_player_pos: Option<(i32, i32)>,
+}
+
+pub fn ai_take_turn(monster: Entity, game: &mut map::Game, world: &mut World) {
+    use Ai::*;
+    if !is_awake(monster, world) {
+        // sleeping monsters may notice the player walking by
+        if sees_player(monster, game, world) && game.rng.gen::<f32>() < global::WAKE_CHANCE_IN_VIEW
+        {
+            wake_up(monster, game, world);
+        }
+        return;
+    }
+    if let Some(ai) = world.ais.remove(monster) {
+        // anyone badly hurt forgets their plans and runs for it
+        let ai = match ai {
+            Fleeing { .. } => ai,
+            ai if is_badly_hurt(monster, world) => {
+                game.messages.add(
+                    format!("The {} turns to flee!", world.name(monster)),
+                    global::FLEE_COLOR,
+                );
+                Fleeing {
+                    previous_ai: Box::new(ai),
+                }
+            }
+            ai => ai,
+        };
+        let new_ai = match ai {
+            Basic => ai_basic(monster, game, world),
+            Ranged { range } => ai_ranged(monster, range, game, world),
+            Pack { alerted } => ai_pack(monster, alerted, game, world),
+            Wandering => ai_wandering(monster, game, world),
+            Necromancer => ai_necromancer(monster, game, world),
+            Fleeing { previous_ai } => ai_fleeing(monster, previous_ai, game, world),
+        };
+        // don't bring back the ai of a monster that died on its own turn
+        if world.fighters.contains(monster) {
+            world.ais.insert(monster, new_ai);
+        }
+    }
+}
+
+/// Wake up every monster within earshot of the noises made since the last
+/// call, and send them to investigate where the noise came from.
+pub fn hear_noises(game: &mut map::Game, world: &mut World) {
+    for noise in game.take_noises() {
+        for entity in world.within_radius(noise.x, noise.y, noise.radius as f32) {
+            if let Some(awareness) = world.awareness.get_mut(entity) {
+                awareness.last_known_player_pos = Some((noise.x, noise.y));
+                if !awareness.awake {
+                    awareness.awake = true;
+                    game.messages.add(
+                        format!("The {} wakes up.", world.name(entity)),
+                        global::WAKE_COLOR,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn is_awake(monster: Entity, world: &World) -> bool {
+    world.awareness.get(monster).map_or(true, |a| a.awake)
+}
+
+fn wake_up(monster: Entity, game: &mut map::Game, world: &mut World) {
+    if let Some(awareness) = world.awareness.get_mut(monster) {
+        awareness.awake = true;
+    }
+    game.messages.add(
+        format!("The {} notices you!", world.name(monster)),
+        global::WAKE_COLOR,
+    );
+}
+
+fn is_badly_hurt(monster: Entity, world: &World) -> bool {
+    world.fighters.get(monster).map_or(false, |f| {
+        f.hp as f32 <= f.max_hp as f32 * global::FLEE_HP_FRACTION
+    })
+}
+
+/// Check the monster's own line of sight to the player. A monster that sees
+/// the player also remembers where they were, so it can follow once they
+/// break line of sight.
+fn sees_player(monster: Entity, game: &mut map::Game, world: &mut World) -> bool {
+    let visible = sight::can_see(monster, world.player, world, &game.map, &mut game.sight);
+    if visible {
+        let player_pos = world.pos(world.player);
+        if let Some(awareness) = world.awareness.get_mut(monster) {
+            awareness.last_known_player_pos = Some(player_pos);
+        }
+    }
+    visible
+}
+
+/// walk up to the player and hit them
+fn chase_and_attack(monster: Entity, game: &mut map::Game, world: &mut World) {
+    if world.distance(monster, world.player) >= 2.0 {
+        // move towards player if far away
+        let (player_x, player_y) = world.pos(world.player);
+        objects::move_towards(monster, player_x, player_y, game, world);
+    } else if world.is_alive(world.player) {
+        // close enough, attack! (if the player is still alive.)
+        objects::attack(monster, world.player, game, world);
+    }
+}
+
+/// Head for the spot the player was last seen (or heard) at. Returns `false`
+/// when there is nothing left to investigate.
+fn investigate(monster: Entity, game: &mut map::Game, world: &mut World) -> bool {
+    let last_known = world
+        .awareness
+        .get(monster)
+        .and_then(|a| a.last_known_player_pos);
+    match last_known {
+        Some(pos) if pos == world.pos(monster) => {
+            // the trail went cold
+            world.awareness[monster].last_known_player_pos = None;
+            false
+        }
+        Some((x, y)) => {
+            objects::move_towards(monster, x, y, game, world);
+            true
+        }
+        None => false,
+    }
+}
+
+fn ai_basic(monster: Entity, game: &mut map::Game, world: &mut World) -> Ai {
+    if sees_player(monster, game, world) {
+        chase_and_attack(monster, game, world);
+    } else {
+        investigate(monster, game, world);
+    }
+    Ai::Basic
+}
+
+fn ai_ranged(monster: Entity, range: i32, game: &mut map::Game, world: &mut World) -> Ai {
+    // keeps its distance and shoots from afar
+    if sees_player(monster, game, world) {
+        let (player_x, player_y) = world.pos(world.player);
+        let distance = world.distance(monster, world.player);
+        if distance < 2.0 && objects::move_away_from(monster, player_x, player_y, game, world) {
+            // backed off out of melee range
+        } else if distance <= range as f32 && world.is_alive(world.player) {
+            objects::shoot(monster, world.player, game, world);
+        } else {
+            objects::move_towards(monster, player_x, player_y, game, world);
+        }
+    } else {
+        investigate(monster, game, world);
+    }
+    Ai::Ranged { range }
+}
+
+fn ai_pack(monster: Entity, alerted: bool, game: &mut map::Game, world: &mut World) -> Ai {
+    let (monster_x, monster_y) = world.pos(monster);
+    let player_pos = world.pos(world.player);
+    let sees_player = sees_player(monster, game, world);
+    if sees_player && !alerted {
+        // howl to call the rest of the pack
+        game.messages.add(
+            format!("The {} howls!", world.name(monster)),
+            global::PACK_HOWL_COLOR,
+        );
+        for other in world.within_radius(monster_x, monster_y, global::PACK_ALERT_RADIUS) {
+            if other == monster {
+                continue;
+            }
+            if let Some(Ai::Pack { ref mut alerted }) = world.ais.get_mut(other) {
+                *alerted = true;
+                world.awareness.insert(
+                    other,
+                    Awareness {
+                        awake: true,
+                        last_known_player_pos: Some(player_pos),
+                    },
+                );
+            }
+        }
+    }
+    if sees_player {
+        chase_and_attack(monster, game, world);
+    } else {
+        investigate(monster, game, world);
+    }
+    Ai::Pack {
+        alerted: alerted || sees_player,
+    }
+}
+
+fn ai_wandering(monster: Entity, game: &mut map::Game, world: &mut World) -> Ai {
+    if sees_player(monster, game, world) {
+        chase_and_attack(monster, game, world);
+    } else if !investigate(monster, game, world) {
+        // nothing to hunt, so amble around
+        let dx = game.rng.gen_range(-1, 2);
+        let dy = game.rng.gen_range(-1, 2);
+        objects::move_by(monster, dx, dy, game, world);
+    }
+    Ai::Wandering
+}
+
+fn ai_necromancer(monster: Entity, game: &mut map::Game, world: &mut World) -> Ai {
+    if !sees_player(monster, game, world) {
+        investigate(monster, game, world);
+        return Ai::Necromancer;
+    }
+    // raise the nearest whole corpse it can see, if any
+    let (x, y) = world.pos(monster);
+    let corpse = world
+        .within_radius(x, y, global::RAISE_DEAD_RANGE)
+        .into_iter()
+        .filter(|&entity| {
+            world
+                .corpses
+                .get(entity)
+                .map_or(false, |c| c.fighter.is_some())
+                && sight::line_of_sight(&game.map, (x, y), world.pos(entity))
+        })
+        .min_by(|&a, &b| {
+            let distance_a = world.objects[a].distance_to_pos(x, y);
+            let distance_b = world.objects[b].distance_to_pos(x, y);
+            distance_a.total_cmp(&distance_b)
+        });
+    if let Some(corpse) = corpse {
+        if corpses::raise_dead(corpse, game, world) {
+            return Ai::Necromancer;
+        }
+    }
+    // otherwise stay out of reach, fighting only when cornered
+    let (player_x, player_y) = world.pos(world.player);
+    let close = world.distance(monster, world.player) < 2.0;
+    if close && !objects::move_away_from(monster, player_x, player_y, game, world) {
+        chase_and_attack(monster, game, world);
+    }
+    Ai::Necromancer
+}
+
+fn ai_fleeing(
+    monster: Entity,
+    previous_ai: Box<Ai>,
+    game: &mut map::Game,
+    world: &mut World,
+) -> Ai {
+    if !is_badly_hurt(monster, world) {
+        // recovered enough to get back to what it was doing
+        return *previous_ai;
+    }
+    if !sees_player(monster, game, world) {
+        // out of sight, so lie low
+        return Ai::Fleeing { previous_ai };
+    }
+    let (player_x, player_y) = world.pos(world.player);
+    let cornered = !objects::move_away_from(monster, player_x, player_y, game, world);
+    if cornered && world.distance(monster, world.player) < 2.0 && world.is_alive(world.player) {
+        // nowhere left to run, so fight back
+        objects::attack(monster, world.player, game, world);
+    }
+    Ai::Fleeing { previous_ai }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::Dice;
+    use crate::objects::{DeathCallback, Fighter, Object};
+    use crate::regeneration::{self, Regeneration};
+    use crate::sight::Vision;
+    use tcod::colors;
+
+    fn fighter(hp: i32) -> Fighter {
+        Fighter {
+            max_hp: 20,
+            hp,
+            defense: 0,
+            power: 0,
+            accuracy: 0,
+            evasion: 0,
+            damage: Dice::new(1, 2),
+            xp: 0,
+            level: 1,
+            on_death: DeathCallback::Monster,
+        }
+    }
+
+    /// an open 20x20 room with the player standing at the given spot
+    fn setup(player_x: i32, player_y: i32) -> (map::Game, World) {
+        let game = map::Game::for_tests(20, 20);
+        let player = Object::new(player_x, player_y, '@', "player", colors::WHITE, true);
+        let mut world = World::new(20, 20, player);
+        world.fighters.insert(
+            world.player,
+            Fighter {
+                max_hp: 1000,
+                hp: 1000,
+                on_death: DeathCallback::Player,
+                ..fighter(1000)
+            },
+        );
+        (game, world)
+    }
+
+    fn add_monster(x: i32, y: i32, ai: Ai, hp: i32, world: &mut World) -> Entity {
+        let monster = world.spawn(Object::new(x, y, 'm', "monster", colors::WHITE, true));
+        world.fighters.insert(monster, fighter(hp));
+        world.ais.insert(monster, ai);
+        world.vision.insert(monster, Vision { radius: 10 });
+        world.awareness.insert(
+            monster,
+            Awareness {
+                awake: true,
+                last_known_player_pos: None,
+            },
+        );
+        monster
+    }
+
+    fn said(game: &map::Game, text: &str) -> bool {
+        game.messages
+            .iter()
+            .any(|(message, _)| message.contains(text))
+    }
+
+    #[test]
+    fn ranged_monster_backs_off_when_the_player_is_close() {
+        let (mut game, mut world) = setup(10, 10);
+        let archer = add_monster(11, 10, Ai::Ranged { range: 6 }, 20, &mut world);
+        ai_take_turn(archer, &mut game, &mut world);
+        assert!(world.distance(archer, world.player) >= 2.0);
+        assert!(!said(&game, "shoots"));
+    }
+
+    #[test]
+    fn ranged_monster_shoots_from_a_distance() {
+        let (mut game, mut world) = setup(10, 10);
+        let archer = add_monster(14, 10, Ai::Ranged { range: 6 }, 20, &mut world);
+        ai_take_turn(archer, &mut game, &mut world);
+        assert_eq!(world.pos(archer), (14, 10));
+        assert!(said(&game, "monster shoots at player"));
+    }
+
+    #[test]
+    fn pack_member_alerts_the_others() {
+        let (mut game, mut world) = setup(5, 5);
+        let leader = add_monster(8, 5, Ai::Pack { alerted: false }, 20, &mut world);
+        // asleep, but within earshot of the leader's howl
+        let follower = add_monster(13, 10, Ai::Pack { alerted: false }, 20, &mut world);
+        world.awareness[follower].awake = false;
+        // too far away to hear it
+        let straggler = add_monster(18, 18, Ai::Pack { alerted: false }, 20, &mut world);
+        world.awareness[straggler].awake = false;
+        ai_take_turn(leader, &mut game, &mut world);
+        assert_eq!(world.ais[straggler], Ai::Pack { alerted: false });
+        assert!(!world.awareness[straggler].awake);
+        assert!(said(&game, "howls"));
+        assert_eq!(world.ais[leader], Ai::Pack { alerted: true });
+        assert_eq!(world.ais[follower], Ai::Pack { alerted: true });
+        assert_eq!(
+            world.awareness[follower],
+            Awareness {
+                awake: true,
+                last_known_player_pos: Some((5, 5)),
+            }
+        );
+    }
+
+    #[test]
+    fn badly_hurt_monster_flees() {
+        let (mut game, mut world) = setup(10, 10);
+        let orc = add_monster(11, 10, Ai::Basic, 4, &mut world);
+        ai_take_turn(orc, &mut game, &mut world);
+        assert!(said(&game, "turns to flee"));
+        assert_eq!(
+            world.ais[orc],
+            Ai::Fleeing {
+                previous_ai: Box::new(Ai::Basic)
+            }
+        );
+        assert!(world.distance(orc, world.player) >= 2.0);
+    }
+
+    #[test]
+    fn healthy_monster_does_not_flee() {
+        let (mut game, mut world) = setup(10, 10);
+        let orc = add_monster(11, 10, Ai::Basic, 6, &mut world);
+        ai_take_turn(orc, &mut game, &mut world);
+        assert_eq!(world.ais[orc], Ai::Basic);
+    }
+
+    #[test]
+    fn fleeing_monster_comes_back_once_healed() {
+        let (mut game, mut world) = setup(10, 10);
+        let orc = add_monster(15, 10, Ai::Basic, 4, &mut world);
+        world
+            .regeneration
+            .insert(orc, Regeneration::new(global::REGENERATION_RATE));
+        ai_take_turn(orc, &mut game, &mut world);
+        assert!(matches!(world.ais[orc], Ai::Fleeing { .. }));
+        for _ in 0..1000 {
+            regeneration::regenerate(&mut world);
+            ai_take_turn(orc, &mut game, &mut world);
+            if world.ais[orc] == Ai::Basic {
+                return;
+            }
+        }
+        panic!("the monster never stopped fleeing");
+    }
+}
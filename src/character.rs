@@ -0,0 +1,207 @@
+use crate::equipment;
+use crate::global;
+use crate::map::Game;
+use crate::menu;
+use crate::world::World;
+use tcod::colors;
+use tcod::console::*;
+
+/// The total experience a fighter of the given level needs to reach the next
+/// one. Every level takes a bit more than the last.
+pub fn next_level_xp(level: i32) -> i32 {
+    (1..=level)
+        .map(|level| global::LEVEL_UP_BASE + level * global::LEVEL_UP_FACTOR)
+        .sum()
+}
+
+/// If the player has earned enough experience, go up a level and let them
+/// pick which stat to raise; as many times over as they've earned.
+pub fn check_level_up(root: &mut Root, game: &mut Game, world: &mut World) {
+    loop {
+        let fighter = match world.fighters.get(world.player) {
+            Some(fighter) => *fighter,
+            None => return,
+        };
+        if fighter.xp < next_level_xp(fighter.level) {
+            return;
+        }
+        let level = fighter.level + 1;
+        world.fighters[world.player].level = level;
+        game.messages.add(
+            format!(
+                "Your battle skills grow stronger! You reached level {}!",
+                level
+            ),
+            colors::YELLOW,
+        );
+        // keep asking until they make a choice
+        let mut choice = None;
+        while choice.is_none() {
+            choice = menu::menu(
+                "Level up! Choose a stat to raise:\n",
+                &[
+                    format!("Constitution (+20 HP, from {})", fighter.max_hp),
+                    format!("Strength (+1 attack, from {})", fighter.power),
+                    format!("Agility (+1 defense, from {})", fighter.defense),
+                ],
+                global::LEVEL_SCREEN_WIDTH,
+                root,
+            );
+        }
+        let fighter = &mut world.fighters[world.player];
+        match choice.unwrap() {
+            0 => {
+                fighter.max_hp += 20;
+                fighter.hp += 20;
+            }
+            1 => fighter.power += 1,
+            _ => fighter.defense += 1,
+        }
+    }
+}
+
+/// Everything about the player in one place: who they are, how strong, what
+/// they're wearing and what's ailing them, along with everything counted over
+/// the run so far. Stays up until a key is pressed.
+pub fn show_character_screen(root: &mut Root, game: &Game, world: &World) {
+    root.set_default_background(colors::BLACK);
+    root.clear();
+    let mut y = 2;
+    let mut print = |root: &mut Root, text: String, color| {
+        root.set_default_foreground(color);
+        root.print_ex(2, y, BackgroundFlag::None, TextAlignment::Left, text);
+        y += 1;
+    };
+    let player = world.player;
+    print(root, "CHARACTER".to_string(), colors::LIGHT_YELLOW);
+    print(root, String::new(), colors::WHITE);
+
+    if let Some(fighter) = world.fighters.get(player) {
+        // each stat with the equipment's share of it spelled out
+        let stat = |name: &str, base: i32, bonus: i32| {
+            if bonus == 0 {
+                format!("{}: {}", name, base)
+            } else {
+                format!("{}: {} ({} {:+})", name, base + bonus, base, bonus)
+            }
+        };
+        print(root, format!("Name: {}", world.name(player)), colors::WHITE);
+        print(root, format!("Level: {}", fighter.level), colors::WHITE);
+        let next = next_level_xp(fighter.level);
+        print(
+            root,
+            format!(
+                "Experience: {} ({} to next level)",
+                fighter.xp,
+                next - fighter.xp
+            ),
+            colors::WHITE,
+        );
+        print(
+            root,
+            stat(
+                "Maximum HP",
+                fighter.max_hp,
+                equipment::max_hp_bonus(player, world),
+            ),
+            colors::WHITE,
+        );
+        print(
+            root,
+            stat(
+                "Attack",
+                fighter.power,
+                equipment::power_bonus(player, world),
+            ),
+            colors::WHITE,
+        );
+        print(
+            root,
+            stat(
+                "Defense",
+                fighter.defense,
+                equipment::defense_bonus(player, world),
+            ),
+            colors::WHITE,
+        );
+    }
+    if let Some(mana) = world.mana.get(player) {
+        print(
+            root,
+            format!("Mana: {}/{}", mana.mana, mana.max_mana),
+            colors::WHITE,
+        );
+    }
+    print(root, format!("Depth: {}", game.depth), colors::WHITE);
+    print(root, String::new(), colors::WHITE);
+
+    print(root, "Spells:".to_string(), colors::LIGHT_YELLOW);
+    let spells = world
+        .spellbooks
+        .get(player)
+        .map_or(vec![], |book| book.spells.clone());
+    if spells.is_empty() {
+        print(root, "  (none)".to_string(), colors::LIGHT_GREY);
+    }
+    for (spell, _) in spells {
+        print(
+            root,
+            format!("  {} ({} mana)", spell.name(), spell.cost()),
+            colors::WHITE,
+        );
+    }
+    print(root, String::new(), colors::WHITE);
+
+    print(root, "Equipment:".to_string(), colors::LIGHT_YELLOW);
+    let equipped = equipment::equipped(player, world);
+    if equipped.is_empty() {
+        print(root, "  (nothing)".to_string(), colors::LIGHT_GREY);
+    }
+    for (item, equipment) in equipped {
+        print(
+            root,
+            format!("  {} (on {})", world.name(item), equipment.slot),
+            colors::WHITE,
+        );
+    }
+    print(root, String::new(), colors::WHITE);
+
+    print(root, "Status:".to_string(), colors::LIGHT_YELLOW);
+    let effects = world
+        .statuses
+        .get(player)
+        .map_or(vec![], |statuses| statuses.iter().collect());
+    if effects.is_empty() {
+        print(root, "  (normal)".to_string(), colors::LIGHT_GREY);
+    }
+    for (effect, turns_left) in effects {
+        print(
+            root,
+            format!("  {} ({} turns)", effect.name(), turns_left),
+            colors::WHITE,
+        );
+    }
+    print(root, String::new(), colors::WHITE);
+
+    print(root, "Statistics:".to_string(), colors::LIGHT_YELLOW);
+    print(
+        root,
+        format!("  Turns played: {}", game.turn),
+        colors::WHITE,
+    );
+    for line in game.stats.lines() {
+        print(root, format!("  {}", line), colors::WHITE);
+    }
+
+    root.set_default_foreground(colors::LIGHT_GREY);
+    let bottom = root.height() - 2;
+    root.print_ex(
+        2,
+        bottom,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        "Press any key to return.",
+    );
+    root.flush();
+    root.wait_for_keypress(true);
+}
@@ -0,0 +1,195 @@
+use crate::global;
+use crate::objects::Fighter;
+use rand::Rng;
+use std::cmp;
+
+/// A handful of identical dice, e.g. `Dice::new(2, 6)` for 2d6.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dice {
+    pub count: i32,
+    pub sides: i32,
+}
+
+impl Dice {
+    pub fn new(count: i32, sides: i32) -> Self {
+        Dice { count, sides }
+    }
+
+    /// roll every die and add up the results
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> i32 {
+        (0..self.count)
+            .map(|_| rng.gen_range(1, cmp::max(self.sides, 1) + 1))
+            .sum()
+    }
+}
+
+/// What happened when one fighter swung at another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttackOutcome {
+    Miss,
+    Hit(i32),
+    Critical(i32),
+}
+
+impl AttackOutcome {
+    /// how much damage got through, if any
+    pub fn damage(self) -> i32 {
+        match self {
+            AttackOutcome::Miss => 0,
+            AttackOutcome::Hit(damage) | AttackOutcome::Critical(damage) => damage,
+        }
+    }
+}
+
+/// Percent chance for an attacker with `accuracy` to hit a target with `evasion`.
+pub fn hit_chance(accuracy: i32, evasion: i32) -> i32 {
+    let chance = global::BASE_TO_HIT + accuracy - evasion;
+    chance.clamp(global::MIN_TO_HIT, global::MAX_TO_HIT)
+}
+
+/// Roll to hit, then roll damage. Criticals roll the damage dice again and
+/// every hit deals at least `MIN_DAMAGE`, so armour can't make anyone immortal.
+pub fn resolve_attack<R: Rng>(
+    attacker: &Fighter,
+    defender: &Fighter,
+    rng: &mut R,
+) -> AttackOutcome {
+    let roll = rng.gen_range(0, 100);
+    if roll >= hit_chance(attacker.accuracy, defender.evasion) {
+        return AttackOutcome::Miss;
+    }
+    let critical = roll < global::CRITICAL_CHANCE;
+    let mut damage = attacker.damage.roll(rng);
+    if critical {
+        for _ in 1..global::CRITICAL_MULTIPLIER {
+            damage += attacker.damage.roll(rng);
+        }
+    }
+    let damage = cmp::max(
+        global::MIN_DAMAGE,
+        damage + attacker.power - defender.defense,
+    );
+    if critical {
+        AttackOutcome::Critical(damage)
+    } else {
+        AttackOutcome::Hit(damage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::DeathCallback;
+    use rand::{SeedableRng, StdRng};
+
+    const ROLLS: i32 = 5000;
+
+    fn rng() -> StdRng {
+        SeedableRng::from_seed(&[42][..])
+    }
+
+    fn fighter(power: i32, defense: i32, accuracy: i32, evasion: i32, damage: Dice) -> Fighter {
+        Fighter {
+            max_hp: 10,
+            hp: 10,
+            defense,
+            power,
+            accuracy,
+            evasion,
+            damage,
+            xp: 0,
+            level: 1,
+            on_death: DeathCallback::Monster,
+        }
+    }
+
+    #[test]
+    fn hit_chance_is_clamped() {
+        assert_eq!(hit_chance(0, 0), global::BASE_TO_HIT);
+        assert_eq!(hit_chance(-1000, 0), global::MIN_TO_HIT);
+        assert_eq!(hit_chance(1000, 0), global::MAX_TO_HIT);
+    }
+
+    #[test]
+    fn hit_rate_tracks_hit_chance() {
+        let mut rng = rng();
+        for &(accuracy, evasion) in &[(0, 0), (10, 40), (-30, 0), (0, 100)] {
+            let attacker = fighter(0, 0, accuracy, 0, Dice::new(1, 4));
+            let defender = fighter(0, 0, 0, evasion, Dice::new(1, 4));
+            let hits = (0..ROLLS)
+                .filter(|_| resolve_attack(&attacker, &defender, &mut rng) != AttackOutcome::Miss)
+                .count();
+            let observed = hits as f32 * 100.0 / ROLLS as f32;
+            let expected = hit_chance(accuracy, evasion) as f32;
+            assert!(
+                (observed - expected).abs() < 3.0,
+                "hit {}% of the time, expected {}%",
+                observed,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn crit_rate_matches_critical_chance() {
+        let mut rng = rng();
+        let attacker = fighter(0, 0, 0, 0, Dice::new(1, 4));
+        let defender = fighter(0, 0, 0, 0, Dice::new(1, 4));
+        let crits = (0..ROLLS)
+            .filter(|_| {
+                matches!(
+                    resolve_attack(&attacker, &defender, &mut rng),
+                    AttackOutcome::Critical(_)
+                )
+            })
+            .count();
+        let observed = crits as f32 * 100.0 / ROLLS as f32;
+        let expected = global::CRITICAL_CHANCE as f32;
+        assert!(
+            (observed - expected).abs() < 1.5,
+            "crit {}% of the time, expected {}%",
+            observed,
+            expected
+        );
+    }
+
+    #[test]
+    fn damage_never_goes_below_the_floor() {
+        let mut rng = rng();
+        // armour far beyond anything the attacker can roll
+        let attacker = fighter(0, 0, 100, 0, Dice::new(1, 2));
+        let defender = fighter(0, 50, 0, 0, Dice::new(1, 2));
+        for _ in 0..ROLLS {
+            match resolve_attack(&attacker, &defender, &mut rng) {
+                AttackOutcome::Miss => {}
+                AttackOutcome::Hit(damage) | AttackOutcome::Critical(damage) => {
+                    assert!(damage >= global::MIN_DAMAGE)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dice_stay_in_range_with_the_expected_mean() {
+        let mut rng = rng();
+        for &(count, sides) in &[(1, 4), (2, 6), (3, 8)] {
+            let dice = Dice::new(count, sides);
+            let mut total = 0;
+            for _ in 0..ROLLS {
+                let roll = dice.roll(&mut rng);
+                assert!(roll >= count && roll <= count * sides, "rolled {}", roll);
+                total += roll;
+            }
+            let mean = total as f32 / ROLLS as f32;
+            let expected = count as f32 * (sides + 1) as f32 / 2.0;
+            assert!(
+                (mean - expected).abs() < expected * 0.05,
+                "{}d{} averaged {}, expected {}",
+                count,
+                sides,
+                mean,
+                expected
+            );
+        }
+    }
+}
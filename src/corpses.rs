@@ -0,0 +1,274 @@
+use crate::ai::{Ai, Awareness};
+use crate::death::Cause;
+use crate::global;
+use crate::hunger;
+use crate::map;
+use crate::objects::{self, DeathCallback, Fighter, Object};
+use crate::regeneration::Regeneration;
+use crate::sight::Vision;
+use crate::status::{self, Effect};
+use crate::world::{Entity, World};
+use tcod::colors::*;
+
+/// Something dead lying around: it rots away over time and can be eaten,
+/// butchered, or raised by a necromancer while it's still whole.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Corpse {
+    /// name of whatever this used to be
+    pub of: String,
+    /// turns until it has rotted away completely
+    pub turns_left: i32,
+    /// the stats it fought with in life; `None` once it's been cut up
+    pub fighter: Option<Fighter>,
+    /// hit points restored by eating it
+    pub nourishment: i32,
+    /// something eating it does to you besides filling you up
+    pub grants: Option<Effect>,
+}
+
+impl Corpse {
+    pub fn is_rotten(&self) -> bool {
+        self.turns_left <= global::CORPSE_ROTTEN_TURNS
+    }
+}
+
+/// the corpse (or piece of one) lying at the given spot, if any
+fn corpse_at(x: i32, y: i32, world: &World) -> Option<Entity> {
+    world
+        .at(x, y)
+        .iter()
+        .rev()
+        .copied()
+        .find(|&entity| world.corpses.contains(entity))
+}
+
+/// Eat whatever lies under the player. Returns `true` if that took a turn.
+pub fn player_eat(game: &mut map::Game, world: &mut World) -> bool {
+    let (x, y) = world.pos(world.player);
+    let entity = match corpse_at(x, y, world) {
+        Some(entity) => entity,
+        None => {
+            game.messages.add("There's nothing here to eat.", WHITE);
+            return false;
+        }
+    };
+    let corpse = &world.corpses[entity];
+    if corpse.nourishment <= 0 {
+        game.messages.add(
+            format!("There's nothing edible left of the {}.", corpse.of),
+            WHITE,
+        );
+        return false;
+    }
+    let corpse = world.corpses.remove(entity).unwrap();
+    world.despawn(entity);
+    // rotten or not, it fills you up
+    hunger::feed(
+        world.player,
+        corpse.nourishment * global::CORPSE_NUTRITION,
+        world,
+    );
+    if corpse.is_rotten() {
+        game.messages.add(
+            format!("You choke down the rotten {}. You feel sick.", corpse.of),
+            global::ROTTEN_COLOR,
+        );
+        objects::take_damage(
+            world.player,
+            global::ROTTEN_FOOD_DAMAGE,
+            Cause::RottenFood,
+            game,
+            world,
+        );
+    } else {
+        game.messages
+            .add(format!("You eat the {}. Delicious!", corpse.of), GREEN);
+        objects::heal(world.player, corpse.nourishment, world);
+        if corpse.grants == Some(Effect::Telepathic) {
+            game.messages.add(
+                "Your head buzzes with the thoughts of others!",
+                LIGHT_VIOLET,
+            );
+            status::add_effect(
+                world.player,
+                Effect::Telepathic,
+                global::TELEPATHY_TURNS,
+                world,
+            );
+        }
+    }
+    true
+}
+
+/// Cut the corpse under the player into pieces of meat, which keep for longer
+/// than a whole body does. Returns `true` if that took a turn.
+pub fn player_butcher(game: &mut map::Game, world: &mut World) -> bool {
+    let (x, y) = world.pos(world.player);
+    let entity = corpse_at(x, y, world).filter(|&entity| world.corpses[entity].fighter.is_some());
+    let entity = match entity {
+        Some(entity) => entity,
+        None => {
+            game.messages.add("There's nothing here to butcher.", WHITE);
+            return false;
+        }
+    };
+    let corpse = world.corpses.remove(entity).unwrap();
+    world.despawn(entity);
+    if corpse.is_rotten() {
+        game.messages.add(
+            format!("The {} falls apart under your knife.", corpse.of),
+            global::ROTTEN_COLOR,
+        );
+        return true;
+    }
+    let pieces = (corpse.nourishment / global::MEAT_NOURISHMENT).max(1);
+    for _ in 0..pieces {
+        let name = format!("piece of {} meat", corpse.of);
+        let meat = world.spawn(Object::new(x, y, '%', &name, global::MEAT_COLOR, false));
+        world.corpses.insert(
+            meat,
+            Corpse {
+                of: name,
+                turns_left: global::MEAT_DECAY_TURNS,
+                fighter: None,
+                nourishment: global::MEAT_NOURISHMENT,
+                grants: None,
+            },
+        );
+    }
+    game.messages.add(
+        format!("You cut the {} into {} pieces.", corpse.of, pieces),
+        WHITE,
+    );
+    true
+}
+
+/// Age every corpse by one turn and clear away the ones that are gone, so dead
+/// things don't pile up in the world forever.
+pub fn rot_corpses(game: &mut map::Game, world: &mut World) {
+    let mut gone = vec![];
+    for (entity, corpse) in world.corpses.iter_mut() {
+        corpse.turns_left -= 1;
+        if corpse.turns_left == global::CORPSE_ROTTEN_TURNS {
+            world.objects[entity].color = global::ROTTEN_COLOR;
+        }
+        if corpse.turns_left <= 0 {
+            gone.push(entity);
+        }
+    }
+    let player_pos = world.pos(world.player);
+    for entity in gone {
+        if world.pos(entity) == player_pos {
+            game.messages.add(
+                format!("The {} has rotted away.", world.name(entity)),
+                global::ROTTEN_COLOR,
+            );
+        }
+        world.despawn(entity);
+    }
+}
+
+/// Turn a whole corpse back into a (weaker) walking, fighting monster.
+/// Returns `false` if the corpse can't be raised.
+pub fn raise_dead(entity: Entity, game: &mut map::Game, world: &mut World) -> bool {
+    let (x, y) = world.pos(entity);
+    // the player doesn't block, but they're in the way all the same
+    if map::is_blocked(x, y, &game.map, world) || world.pos(world.player) == (x, y) {
+        return false;
+    }
+    let fighter = match world.corpses.get(entity).and_then(|c| c.fighter) {
+        Some(fighter) => fighter,
+        None => return false,
+    };
+    let corpse = world.corpses.remove(entity).unwrap();
+    let zombie = &mut world.objects[entity];
+    zombie.name = format!("zombie {}", corpse.of);
+    zombie.character = 'Z';
+    zombie.color = global::ZOMBIE_COLOR;
+    zombie.blocks = true;
+    let max_hp = (fighter.max_hp / 2).max(1);
+    world.fighters.insert(
+        entity,
+        Fighter {
+            max_hp,
+            hp: max_hp,
+            evasion: fighter.evasion - 10,
+            on_death: DeathCallback::Undead,
+            ..fighter
+        },
+    );
+    world.ais.insert(entity, Ai::Basic);
+    world.awareness.insert(
+        entity,
+        Awareness {
+            awake: true,
+            last_known_player_pos: None,
+        },
+    );
+    world.vision.insert(
+        entity,
+        Vision {
+            radius: global::ZOMBIE_SIGHT_RADIUS,
+        },
+    );
+    world
+        .regeneration
+        .insert(entity, Regeneration::new(global::REGENERATION_RATE));
+    game.messages.add(
+        format!("The remains of the {} rise again!", corpse.of),
+        global::ZOMBIE_COLOR,
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::Dice;
+
+    /// an orc's corpse lying at the given spot, with the player at (5, 5)
+    fn setup(x: i32, y: i32) -> (map::Game, World, Entity) {
+        let game = map::Game::for_tests(10, 10);
+        let mut world = World::new(10, 10, Object::new(5, 5, '@', "player", WHITE, false));
+        let orc = world.spawn(Object::new(x, y, '%', "remains of orc", WHITE, false));
+        world.corpses.insert(
+            orc,
+            Corpse {
+                of: "orc".to_string(),
+                turns_left: global::CORPSE_DECAY_TURNS,
+                fighter: Some(Fighter {
+                    max_hp: 10,
+                    hp: 0,
+                    defense: 0,
+                    power: 3,
+                    accuracy: 0,
+                    evasion: 0,
+                    damage: Dice::new(1, 4),
+                    xp: 35,
+                    level: 1,
+                    on_death: DeathCallback::Monster,
+                }),
+                nourishment: 5,
+                grants: None,
+            },
+        );
+        (game, world, orc)
+    }
+
+    #[test]
+    fn corpse_rises_as_a_zombie() {
+        let (mut game, mut world, orc) = setup(3, 3);
+        assert!(raise_dead(orc, &mut game, &mut world));
+        assert_eq!(world.name(orc), "zombie orc");
+        assert!(world.fighters.contains(orc));
+        assert!(!world.corpses.contains(orc));
+    }
+
+    #[test]
+    fn corpse_under_the_player_stays_down() {
+        let (mut game, mut world, orc) = setup(5, 5);
+        assert!(!raise_dead(orc, &mut game, &mut world));
+        assert!(world.corpses.contains(orc));
+        assert!(!world.fighters.contains(orc));
+    }
+}
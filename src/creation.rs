@@ -0,0 +1,180 @@
+use crate::combat::Dice;
+use crate::global;
+use crate::items::{self, Item};
+use crate::magic::Spell;
+use crate::menu;
+use crate::objects::{DeathCallback, Fighter};
+use crate::world::World;
+use tcod::colors;
+use tcod::console::*;
+use tcod::input::KeyCode;
+
+/// What the player trained as before setting off, which decides how they
+/// fight and what they bring along.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Class {
+    Warrior,
+    Rogue,
+    Mage,
+}
+
+const CLASSES: [Class; 3] = [Class::Warrior, Class::Rogue, Class::Mage];
+
+impl Class {
+    pub fn name(self) -> &'static str {
+        match self {
+            Class::Warrior => "warrior",
+            Class::Rogue => "rogue",
+            Class::Mage => "mage",
+        }
+    }
+
+    /// a line for the class menu
+    fn description(self) -> &'static str {
+        match self {
+            Class::Warrior => "Warrior: tough, armed and armoured",
+            Class::Rogue => "Rogue: quick, quiet and hard to hit",
+            Class::Mage => "Mage: frail, but knows a few spells",
+        }
+    }
+
+    /// how the class starts out in a fight
+    pub fn fighter(self) -> Fighter {
+        let (max_hp, defense, power, accuracy, evasion, damage) = match self {
+            Class::Warrior => (40, 2, 3, 10, 5, Dice::new(1, 4)),
+            Class::Rogue => (30, 1, 2, 15, 20, Dice::new(1, 4)),
+            Class::Mage => (20, 0, 1, 5, 10, Dice::new(1, 3)),
+        };
+        Fighter {
+            max_hp,
+            hp: max_hp,
+            defense,
+            power,
+            accuracy,
+            evasion,
+            damage,
+            xp: 0,
+            level: 1,
+            on_death: DeathCallback::Player,
+        }
+    }
+
+    /// how hard the class is for monsters to spot
+    pub fn stealth(self) -> i32 {
+        match self {
+            Class::Rogue => 2,
+            Class::Warrior | Class::Mage => 0,
+        }
+    }
+
+    /// how much mana the class can store up for casting
+    pub fn max_mana(self) -> i32 {
+        match self {
+            Class::Warrior => 5,
+            Class::Rogue => 10,
+            Class::Mage => 30,
+        }
+    }
+
+    /// the spells the class knows from the start
+    pub fn spells(self) -> &'static [Spell] {
+        match self {
+            Class::Warrior => &[],
+            Class::Rogue => &[Spell::Blink],
+            Class::Mage => &[Spell::Bolt, Spell::Heal],
+        }
+    }
+
+    /// what the class carries from the start; equipment comes ready to use
+    fn starting_items(self) -> &'static [Item] {
+        match self {
+            Class::Warrior => &[Item::Sword, Item::Shield],
+            Class::Rogue => &[Item::Dagger, Item::Ration],
+            Class::Mage => &[Item::HealingPotion, Item::HealingPotion],
+        }
+    }
+}
+
+/// put the class's starting items in the player's inventory
+pub fn give_starting_items(class: Class, world: &mut World) {
+    let (x, y) = world.pos(world.player);
+    for &item in class.starting_items() {
+        let item = items::spawn_item(item, x, y, world);
+        world.pick_up(item);
+        if let Some(equipment) = world.equipment.get_mut(item) {
+            equipment.equipped = true;
+        }
+    }
+}
+
+/// Ask for the player's name, one key at a time. Returns `None` if they
+/// press Escape to go back.
+fn enter_name(root: &mut Root) -> Option<String> {
+    let mut name = String::new();
+    loop {
+        root.set_default_background(colors::BLACK);
+        root.clear();
+        let center_x = root.width() / 2;
+        let y = root.height() / 3;
+        root.set_default_foreground(colors::LIGHT_YELLOW);
+        root.print_ex(
+            center_x,
+            y,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            "What is your name, adventurer?",
+        );
+        root.set_default_foreground(colors::WHITE);
+        root.print_ex(
+            center_x,
+            y + 2,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            format!("{}_", name),
+        );
+        root.set_default_foreground(colors::LIGHT_GREY);
+        root.print_ex(
+            center_x,
+            y + 4,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            "Enter to continue, Escape to go back.",
+        );
+        root.flush();
+
+        let key = root.wait_for_keypress(true);
+        match key.code {
+            KeyCode::Escape => return None,
+            KeyCode::Enter if !name.trim().is_empty() => return Some(name.trim().to_string()),
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            _ => {
+                let letter = key.printable;
+                if (letter.is_alphanumeric() || letter == ' ' || letter == '-')
+                    && name.len() < global::MAX_NAME_LENGTH
+                {
+                    name.push(letter);
+                }
+            }
+        }
+        if root.window_closed() {
+            return None;
+        }
+    }
+}
+
+/// Have the player name their character and pick a class. Returns `None` if
+/// they back out at either step.
+pub fn create_character(root: &mut Root) -> Option<(String, Class)> {
+    let name = enter_name(root)?;
+    root.set_default_background(colors::BLACK);
+    root.clear();
+    let options = CLASSES
+        .iter()
+        .map(|class| class.description())
+        .collect::<Vec<_>>();
+    let header = format!("Choose a class for {}:\n", name);
+    let choice = menu::menu(&header, &options, global::CLASS_MENU_WIDTH, root)?;
+    Some((name, CLASSES[choice]))
+}
@@ -0,0 +1,163 @@
+use crate::equipment;
+use crate::global;
+use crate::map::Game;
+use crate::utils;
+use crate::world::World;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use tcod::colors;
+use tcod::console::*;
+
+/// Whatever dealt some damage, so that if it turns out to be deadly the
+/// player can be told what killed them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cause {
+    /// a monster of the given name
+    Monster(String),
+    Lava,
+    Burning,
+    SpikeTrap,
+    RottenFood,
+    Starvation,
+}
+
+impl Cause {
+    /// how it reads after "you were...", e.g. "killed by an orc"
+    pub fn describe(&self) -> String {
+        match self {
+            Cause::Monster(name) => format!("killed by {}", utils::with_article(name)),
+            Cause::Lava => "burned up in lava".to_string(),
+            Cause::Burning => "burned to death".to_string(),
+            Cause::SpikeTrap => "impaled on a spike trap".to_string(),
+            Cause::RottenFood => "poisoned by rotten food".to_string(),
+            Cause::Starvation => "starved to death".to_string(),
+        }
+    }
+}
+
+/// the cause of death with where it happened, like "killed by a troll on level 3"
+pub fn death_summary(game: &Game) -> String {
+    let cause = game
+        .death
+        .as_ref()
+        .map_or("died".to_string(), Cause::describe);
+    format!("{} on level {}", cause, game.depth)
+}
+
+/// Everything worth remembering about a finished game, as plain text.
+fn morgue_text(game: &Game, world: &World) -> String {
+    let mut text = String::new();
+    text += &format!("{}, {}.\n\n", world.name(world.player), death_summary(game));
+    text += &format!("Seed: {}\n", game.seed);
+    text += &format!("Depth: {}\n", game.depth);
+    text += &format!("Turns: {}\n\n", game.turn);
+
+    if let Some(&fighter) = world.fighters.get(world.player) {
+        let fighter = equipment::with_bonuses(world.player, fighter, world);
+        text += "Final stats:\n";
+        text += &format!("  Level: {} ({} XP)\n", fighter.level, fighter.xp);
+        text += &format!("  HP: {}/{}\n", fighter.hp.max(0), fighter.max_hp);
+        text += &format!("  Power: {}\n", fighter.power);
+        text += &format!("  Defense: {}\n", fighter.defense);
+        text += &format!("  Accuracy: {}\n", fighter.accuracy);
+        text += &format!("  Evasion: {}\n", fighter.evasion);
+        text += &format!(
+            "  Damage: {}d{}\n\n",
+            fighter.damage.count, fighter.damage.sides
+        );
+    }
+
+    text += "Statistics:\n";
+    for line in game.stats.lines() {
+        text += &format!("  {}\n", line);
+    }
+    text += "\n";
+
+    text += "Inventory:\n";
+    if world.inventory.is_empty() {
+        text += "  (nothing)\n";
+    }
+    for &item in &world.inventory {
+        match world.equipment.get(item) {
+            Some(equipment) if equipment.equipped => {
+                text += &format!("  {} (on {})\n", world.name(item), equipment.slot)
+            }
+            _ => text += &format!("  {}\n", world.name(item)),
+        }
+    }
+
+    text += "\nLast messages:\n";
+    let messages = game
+        .messages
+        .iter()
+        .map(|(message, _)| message)
+        .collect::<Vec<_>>();
+    let first = messages.len().saturating_sub(global::MORGUE_MESSAGES);
+    for message in &messages[first..] {
+        text += &format!("  {}\n", message);
+    }
+    text
+}
+
+/// Write the morgue file for a finished game into the current directory,
+/// returning its name. An older morgue file is never written over.
+pub fn write_morgue(game: &Game, world: &World) -> io::Result<String> {
+    // the seed and turn tell most games apart; replaying a seed can still
+    // end on the same turn, so count up until there's a free name
+    let name = format!("morgue-{}-{}", game.seed, game.turn);
+    let mut copy = 1;
+    loop {
+        let path = if copy == 1 {
+            format!("{}.txt", name)
+        } else {
+            format!("{}-{}.txt", name, copy)
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(morgue_text(game, world).as_bytes())?;
+                return Ok(path);
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => copy += 1,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Show what killed the player and where the morgue file went, and wait for a
+/// key.
+pub fn show_game_over(root: &mut Root, game: &Game, world: &World, morgue: &io::Result<String>) {
+    root.set_default_background(colors::BLACK);
+    root.clear();
+    let center_x = root.width() / 2;
+    let mut y = root.height() / 3;
+    let mut print = |root: &mut Root, text: String, color| {
+        root.set_default_foreground(color);
+        root.print_ex(
+            center_x,
+            y,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            text,
+        );
+        y += 2;
+    };
+    print(root, "YOU DIED".to_string(), colors::RED);
+    print(
+        root,
+        format!("{}, {},", world.name(world.player), death_summary(game)),
+        colors::WHITE,
+    );
+    print(root, format!("after {} turns.", game.turn), colors::WHITE);
+    let saved = match morgue {
+        Ok(path) => format!("Your story has been written to {}.", path),
+        Err(error) => format!("Couldn't write the morgue file: {}", error),
+    };
+    print(root, saved, colors::LIGHT_GREY);
+    print(
+        root,
+        "Press any key to continue.".to_string(),
+        colors::LIGHT_GREY,
+    );
+    root.flush();
+    root.wait_for_keypress(true);
+}
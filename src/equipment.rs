@@ -0,0 +1,133 @@
+use crate::map;
+use crate::objects::Fighter;
+use crate::world::{Entity, World};
+use std::fmt;
+use tcod::colors::*;
+
+/// Where on the body a piece of equipment goes. Only one thing fits in each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    LeftHand,
+    RightHand,
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Slot::LeftHand => write!(f, "left hand"),
+            Slot::RightHand => write!(f, "right hand"),
+        }
+    }
+}
+
+/// An item that makes its wearer better at fighting while it's equipped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Equipment {
+    pub slot: Slot,
+    pub equipped: bool,
+    pub power_bonus: i32,
+    pub defense_bonus: i32,
+    pub max_hp_bonus: i32,
+}
+
+/// everything the entity has equipped. Only the player carries anything, so
+/// for a monster this is always empty
+pub fn equipped(entity: Entity, world: &World) -> Vec<(Entity, Equipment)> {
+    if entity != world.player {
+        return vec![];
+    }
+    world
+        .inventory
+        .iter()
+        .filter_map(|&item| world.equipment.get(item).map(|&e| (item, e)))
+        .filter(|(_, equipment)| equipment.equipped)
+        .collect()
+}
+
+pub fn power_bonus(entity: Entity, world: &World) -> i32 {
+    equipped(entity, world)
+        .iter()
+        .map(|(_, e)| e.power_bonus)
+        .sum()
+}
+
+pub fn defense_bonus(entity: Entity, world: &World) -> i32 {
+    equipped(entity, world)
+        .iter()
+        .map(|(_, e)| e.defense_bonus)
+        .sum()
+}
+
+pub fn max_hp_bonus(entity: Entity, world: &World) -> i32 {
+    equipped(entity, world)
+        .iter()
+        .map(|(_, e)| e.max_hp_bonus)
+        .sum()
+}
+
+/// the most hit points the entity can have, counting its equipment
+pub fn max_hp(entity: Entity, world: &World) -> i32 {
+    world.fighters.get(entity).map_or(0, |f| f.max_hp) + max_hp_bonus(entity, world)
+}
+
+/// the entity's fighting stats with its equipment's bonuses added on
+pub fn with_bonuses(entity: Entity, fighter: Fighter, world: &World) -> Fighter {
+    Fighter {
+        max_hp: fighter.max_hp + max_hp_bonus(entity, world),
+        power: fighter.power + power_bonus(entity, world),
+        defense: fighter.defense + defense_bonus(entity, world),
+        ..fighter
+    }
+}
+
+/// the item the player has equipped in the given slot, if any
+pub fn equipped_in_slot(slot: Slot, world: &World) -> Option<Entity> {
+    equipped(world.player, world)
+        .into_iter()
+        .find(|(_, equipment)| equipment.slot == slot)
+        .map(|(item, _)| item)
+}
+
+/// equip the item if it isn't already, or take it off if it is
+pub fn toggle(item: Entity, game: &mut map::Game, world: &mut World) {
+    let equipment = match world.equipment.get(item) {
+        Some(equipment) => *equipment,
+        None => return,
+    };
+    if equipment.equipped {
+        dequip(item, game, world);
+    } else {
+        // make room for it first
+        if let Some(old) = equipped_in_slot(equipment.slot, world) {
+            dequip(old, game, world);
+        }
+        equip(item, game, world);
+    }
+}
+
+fn equip(item: Entity, game: &mut map::Game, world: &mut World) {
+    let slot = world.equipment[item].slot;
+    world.equipment[item].equipped = true;
+    game.messages.add(
+        format!("Equipped {} on {}.", world.name(item), slot),
+        LIGHT_GREEN,
+    );
+}
+
+/// take the item off, if it's equipped
+pub fn dequip(item: Entity, game: &mut map::Game, world: &mut World) {
+    let slot = match world.equipment.get(item) {
+        Some(equipment) if equipment.equipped => equipment.slot,
+        _ => return,
+    };
+    world.equipment[item].equipped = false;
+    game.messages.add(
+        format!("Dequipped {} from {}.", world.name(item), slot),
+        LIGHT_YELLOW,
+    );
+    // hit points that came with it go with it
+    let max_hp = max_hp(world.player, world);
+    if let Some(fighter) = world.fighters.get_mut(world.player) {
+        fighter.hp = fighter.hp.min(max_hp);
+    }
+}
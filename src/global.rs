@@ -2,28 +2,169 @@ use tcod::colors;
 use tcod::colors::Color;
 use tcod::map::FovAlgorithm;
 
+// window size unless another one is given with `--size <width>x<height>`
 pub const SCREEN_WIDTH: i32 = 80;
 pub const SCREEN_HEIGHT: i32 = 50;
-pub const MAP_WIDTH: i32 = 80;
-pub const MAP_HEIGHT: i32 = 43;
+pub const MIN_SCREEN_WIDTH: i32 = 40;
+pub const MIN_SCREEN_HEIGHT: i32 = 20;
+// size of a level
+pub const MAP_WIDTH: i32 = 200;
+pub const MAP_HEIGHT: i32 = 200;
 pub const PANEL_HEIGHT: i32 = 7;
 pub const BAR_WIDTH: i32 = 20;
-pub const PANEL_Y: i32 = SCREEN_HEIGHT - PANEL_HEIGHT;
-pub const MSG_X: i32 = BAR_WIDTH + 2;
-pub const MSG_WIDTH: i32 = SCREEN_WIDTH - BAR_WIDTH - 2;
-pub const MSG_HEIGHT: usize = PANEL_HEIGHT as usize - 1;
 
 pub const LIMIT_FPS: i32 = 20; // 20 frames-per-second maximum
+
+// how the player's field of view starts out; it can change during the game
 pub const FOV_ALGO: FovAlgorithm = FovAlgorithm::Basic;
 pub const FOV_LIGHT_WALLS: bool = true;
-pub const TORCH_RADIUS: i32 = 10;
-pub const PLAYER: usize = 0;
+// how far the player can see, given enough light
+pub const SIGHT_RADIUS: i32 = 30;
+// how far a blinded player can still feel their way around
+pub const BLIND_SIGHT_RADIUS: i32 = 1;
+// how far away a telepathic player senses other minds
+pub const TELEPATHY_RADIUS: f32 = 20.0;
+pub const TELEPATHY_TURNS: i32 = 200;
+// how far a player lit up by a scroll of light sees, and for how long
+pub const RADIANT_SIGHT_RADIUS: i32 = 45;
+pub const RADIANT_TURNS: i32 = 100;
+
+// light
+pub const AMBIENT_LIGHT: f32 = 0.05;
+pub const MIN_VISIBLE_LIGHT: f32 = 0.15;
+pub const TORCH_RADIUS: i32 = 8;
+pub const TORCH_INTENSITY: f32 = 1.0;
+pub const TORCH_FUEL: i32 = 2000;
+pub const TORCH_LOW_FUEL: i32 = 100;
+pub const EMBER_RADIUS: i32 = 2;
+pub const EMBER_INTENSITY: f32 = 0.5;
+pub const RADIANT_LIGHT_RADIUS: i32 = 16;
+pub const BRAZIER_CHANCE: f32 = 0.25;
+pub const BRAZIER_RADIUS: i32 = 6;
+pub const BRAZIER_INTENSITY: f32 = 0.9;
+pub const GLOW_RADIUS: i32 = 3;
+pub const GLOW_INTENSITY: f32 = 0.5;
 
 pub const ROOM_MAX_SIZE: i32 = 10;
 pub const ROOM_MIN_SIZE: i32 = 6;
-pub const MAX_ROOMS: i32 = 30;
+pub const MAX_ROOMS: i32 = 300;
+// chances of a room having a patch of some special terrain in it
+pub const POOL_CHANCE: f32 = 0.15;
+pub const LAVA_CHANCE: f32 = 0.05;
+pub const RUBBLE_CHANCE: f32 = 0.15;
+pub const MAX_PATCH_SIZE: i32 = 8;
 
 pub const MAX_ROOM_MONSTERS: i32 = 3;
+pub const JACKAL_PACK_SIZE: i32 = 3;
+
+// monster AI
+pub const FLEE_HP_FRACTION: f32 = 0.25;
+pub const PACK_ALERT_RADIUS: f32 = 8.0;
+pub const ARCHER_RANGE: i32 = 6;
+pub const ASLEEP_CHANCE: f32 = 0.5;
+pub const WAKE_CHANCE_IN_VIEW: f32 = 0.3;
+pub const COMBAT_NOISE_RADIUS: i32 = 10;
+pub const RAISE_DEAD_RANGE: f32 = 6.0;
+pub const ZOMBIE_SIGHT_RADIUS: i32 = 5;
+
+// corpses and what's left of them
+pub const CORPSE_DECAY_TURNS: i32 = 300;
+pub const CORPSE_ROTTEN_TURNS: i32 = 100;
+pub const MEAT_DECAY_TURNS: i32 = 600;
+pub const MEAT_NOURISHMENT: i32 = 3;
+pub const ROTTEN_FOOD_DAMAGE: i32 = 4;
+
+// hunger: the player burns a point of nutrition every turn
+pub const START_NUTRITION: i32 = 1200;
+pub const MAX_NUTRITION: i32 = 2000;
+pub const HUNGRY_NUTRITION: i32 = 300;
+pub const WEAK_NUTRITION: i32 = 100;
+pub const FAINTING_NUTRITION: i32 = 30;
+// nutrition gained for every hit point a corpse would heal
+pub const CORPSE_NUTRITION: i32 = 40;
+pub const RATION_NUTRITION: i32 = 800;
+pub const WEAK_POWER_PENALTY: i32 = 1;
+pub const WEAK_ACCURACY_PENALTY: i32 = 10;
+pub const FAINT_CHANCE: f32 = 0.1;
+pub const FAINT_TURNS: i32 = 3;
+
+// natural healing: the share of maximum hit points that comes back every
+// turn, and the least any fighter heals per turn however frail
+pub const REGENERATION_RATE: f32 = 0.003;
+pub const MIN_REGENERATION: f32 = 0.02;
+
+// items
+pub const RATION_CHANCE: f32 = 0.1;
+pub const POTION_CHANCE: f32 = 0.1;
+pub const SWORD_CHANCE: f32 = 0.03;
+pub const SHIELD_CHANCE: f32 = 0.03;
+pub const LIGHTNING_SCROLL_CHANCE: f32 = 0.05;
+pub const MAPPING_SCROLL_CHANCE: f32 = 0.03;
+pub const LIGHT_SCROLL_CHANCE: f32 = 0.03;
+pub const BOOK_CHANCE: f32 = 0.03;
+pub const HEAL_AMOUNT: i32 = 10;
+pub const INVENTORY_SIZE: usize = 26;
+pub const INVENTORY_WIDTH: i32 = 50;
+
+// magic: what each spell costs in mana, how many turns it takes to be
+// ready again, and how far it reaches
+pub const MANA_REGENERATION: f32 = 0.2;
+pub const BOLT_COST: i32 = 5;
+pub const BOLT_COOLDOWN: i32 = 3;
+pub const BOLT_RANGE: f32 = 8.0;
+pub const BOLT_DAMAGE: i32 = 12;
+pub const HEAL_COST: i32 = 8;
+pub const HEAL_COOLDOWN: i32 = 10;
+pub const BLINK_COST: i32 = 6;
+pub const BLINK_COOLDOWN: i32 = 8;
+pub const BLINK_RANGE: f32 = 6.0;
+pub const SLOW_COST: i32 = 4;
+pub const SLOW_COOLDOWN: i32 = 5;
+pub const SLOW_RANGE: f32 = 8.0;
+pub const SLOW_TURNS: i32 = 10;
+pub const SPELL_MENU_WIDTH: i32 = 40;
+
+// character creation
+pub const MAX_NAME_LENGTH: usize = 16;
+pub const CLASS_MENU_WIDTH: i32 = 40;
+
+// experience and level-ups
+pub const LEVEL_UP_BASE: i32 = 200;
+pub const LEVEL_UP_FACTOR: i32 = 150;
+pub const LEVEL_SCREEN_WIDTH: i32 = 40;
+
+// how many of the last messages go into the morgue file
+pub const MORGUE_MESSAGES: usize = 20;
+pub const HIGH_SCORE_FILE: &str = "highscores.txt";
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const SCORE_PER_DEPTH: i32 = 100;
+pub const MAIN_MENU_WIDTH: i32 = 24;
+
+// combat: percent chances for to-hit rolls and criticals
+pub const BASE_TO_HIT: i32 = 70;
+pub const MIN_TO_HIT: i32 = 5;
+pub const MAX_TO_HIT: i32 = 95;
+pub const CRITICAL_CHANCE: i32 = 5;
+pub const CRITICAL_MULTIPLIER: i32 = 2;
+pub const MIN_DAMAGE: i32 = 1;
+
+// doors
+pub const DOOR_CHANCE: f32 = 0.6;
+pub const DOOR_NOISE_RADIUS: i32 = 5;
+
+// hazards
+pub const LAVA_DAMAGE: i32 = 8;
+pub const BURN_DAMAGE: i32 = 2;
+pub const BURN_TURNS: i32 = 4;
+pub const TRAP_CHANCE: f32 = 0.2;
+pub const SPIKE_TRAP_DAMAGE: i32 = 6;
+pub const ALARM_NOISE_RADIUS: i32 = 25;
+pub const FLASH_BLIND_TURNS: i32 = 12;
+pub const SEARCH_CHANCE: f32 = 0.4;
+// how far monsters will go out of their way to reach the player
+pub const MAX_PATH_COST: i32 = 40;
+// extra path cost for walking around another monster rather than waiting
+pub const CROWD_PATH_COST: i32 = 5;
 
 pub const COLOR_DARK_WALL: Color = Color { r: 0, g: 0, b: 100 };
 pub const COLOR_DARK_GROUND: Color = Color {
@@ -31,7 +172,43 @@ pub const COLOR_DARK_GROUND: Color = Color {
     g: 50,
     b: 150,
 };
+pub const COLOR_SHALLOW_WATER: Color = Color {
+    r: 20,
+    g: 60,
+    b: 170,
+};
+pub const COLOR_DEEP_WATER: Color = Color {
+    r: 0,
+    g: 20,
+    b: 120,
+};
+pub const COLOR_LAVA: Color = Color {
+    r: 160,
+    g: 40,
+    b: 0,
+};
+pub const COLOR_DOOR: Color = colors::DARK_SEPIA;
+pub const COLOR_RUBBLE: Color = colors::GREY;
+pub const TRAP_COLOR: Color = colors::LIGHT_RED;
+pub const BRAZIER_COLOR: Color = colors::FLAME;
 
 pub const ORC_COLOR: Color = colors::DESATURATED_GREEN;
 pub const TROLL_COLOR: Color = colors::DARKER_GREEN;
+pub const GOBLIN_ARCHER_COLOR: Color = colors::DARK_YELLOW;
+pub const JACKAL_COLOR: Color = colors::DARK_ORANGE;
+pub const RAT_COLOR: Color = colors::DARK_SEPIA;
+pub const FLEE_COLOR: Color = colors::LIGHT_YELLOW;
+pub const PACK_HOWL_COLOR: Color = colors::ORANGE;
+pub const WAKE_COLOR: Color = colors::LIGHT_YELLOW;
 pub const DEAD_COLOR: Color = colors::DARK_RED;
+pub const ROTTEN_COLOR: Color = colors::DARKER_SEPIA;
+pub const MEAT_COLOR: Color = colors::LIGHT_RED;
+pub const RATION_COLOR: Color = colors::SEPIA;
+pub const POTION_COLOR: Color = colors::VIOLET;
+pub const SWORD_COLOR: Color = colors::SKY;
+pub const SHIELD_COLOR: Color = colors::DARKER_ORANGE;
+pub const DAGGER_COLOR: Color = colors::LIGHT_SKY;
+pub const SCROLL_COLOR: Color = colors::LIGHT_YELLOW;
+pub const BOOK_COLOR: Color = colors::LIGHT_BLUE;
+pub const NECROMANCER_COLOR: Color = colors::DARK_VIOLET;
+pub const ZOMBIE_COLOR: Color = colors::DARK_LIME;
@@ -0,0 +1,82 @@
+use std::ops::{Index, IndexMut};
+
+/// A rectangle of cells, one per map tile, stored row by row in a single
+/// `Vec` and addressed by `(x, y)`.
+#[derive(Clone, Debug)]
+pub struct Grid<T> {
+    width: i32,
+    height: i32,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// a grid with every cell set to `fill`
+    pub fn new(width: i32, height: i32, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; (width * height) as usize],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    fn offset(&self, x: i32, y: i32) -> Option<usize> {
+        if self.in_bounds(x, y) {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// the cell at the given spot, or `None` if it lies outside the grid
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        self.offset(x, y).map(|offset| &self.cells[offset])
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+        self.offset(x, y).map(move |offset| &mut self.cells[offset])
+    }
+
+    /// every cell along with its position
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(offset, cell)| ((offset as i32 % width, offset as i32 / width), cell))
+    }
+
+    /// the positions of the (up to eight) cells around the given one
+    pub fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(move |&pos| pos != (x, y) && self.in_bounds(pos.0, pos.1))
+    }
+}
+
+impl<T> Index<(i32, i32)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (i32, i32)) -> &T {
+        self.get(x, y).expect("position outside of the grid")
+    }
+}
+
+impl<T> IndexMut<(i32, i32)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (i32, i32)) -> &mut T {
+        self.get_mut(x, y).expect("position outside of the grid")
+    }
+}
@@ -0,0 +1,145 @@
+use crate::death::Cause;
+use crate::global;
+use crate::map;
+use crate::objects;
+use crate::status::{self, Effect};
+use crate::terrain::Terrain;
+use crate::world::{Entity, World};
+use rand::Rng;
+use tcod::colors::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrapKind {
+    /// hurts whoever steps on it
+    Spikes,
+    /// makes a racket that wakes up half the level
+    Alarm,
+    /// goes off in a blinding flash of light
+    Flash,
+}
+
+/// A trap lying in wait. Hidden ones aren't drawn until found by searching,
+/// or by stepping on them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub hidden: bool,
+}
+
+/// the trap at the given spot, if any
+pub fn trap_at(x: i32, y: i32, world: &World) -> Option<Entity> {
+    world
+        .at(x, y)
+        .iter()
+        .copied()
+        .find(|&entity| world.traps.contains(entity))
+}
+
+pub fn is_hidden(entity: Entity, world: &World) -> bool {
+    world.traps.get(entity).map_or(false, |trap| trap.hidden)
+}
+
+/// Whatever happens to something that just stepped onto a tile: wading and
+/// climbing cost extra time, water puts out flames, lava burns, and the player
+/// sets off traps.
+pub fn enter_tile(entity: Entity, from: Terrain, game: &mut map::Game, world: &mut World) {
+    let (x, y) = world.pos(entity);
+    let terrain = game.map[(x, y)].terrain;
+    let is_player = entity == world.player;
+    status::delay(entity, terrain.info().move_cost - 1, world);
+
+    match terrain {
+        Terrain::ShallowWater | Terrain::DeepWater => {
+            if status::remove_effect(entity, Effect::Burning, world) {
+                game.messages.add(
+                    format!("The water puts out the flames on {}.", world.name(entity)),
+                    LIGHT_BLUE,
+                );
+            }
+            if is_player && from != terrain {
+                let message = if terrain == Terrain::DeepWater {
+                    "You swim into the deep water."
+                } else {
+                    "You wade into the water."
+                };
+                game.messages.add(message, LIGHT_BLUE);
+            }
+        }
+        Terrain::Lava => {
+            game.messages.add(
+                format!(
+                    "{} is scorched by the lava for {} hit points!",
+                    world.name(entity),
+                    global::LAVA_DAMAGE
+                ),
+                ORANGE,
+            );
+            status::add_effect(entity, Effect::Burning, global::BURN_TURNS, world);
+            objects::take_damage(entity, global::LAVA_DAMAGE, Cause::Lava, game, world);
+        }
+        Terrain::Rubble if is_player && from != terrain => {
+            game.messages.add("You clamber over the rubble.", WHITE);
+        }
+        _ => {}
+    }
+
+    if is_player {
+        if let Some(trap) = trap_at(x, y, world) {
+            spring_trap(trap, game, world);
+        }
+    }
+}
+
+fn spring_trap(trap: Entity, game: &mut map::Game, world: &mut World) {
+    let (x, y) = world.pos(trap);
+    let kind = world.traps[trap].kind;
+    world.traps[trap].hidden = false;
+    game.messages
+        .add(format!("You step on a {}!", world.name(trap)), RED);
+    match kind {
+        TrapKind::Spikes => {
+            objects::take_damage(
+                world.player,
+                global::SPIKE_TRAP_DAMAGE,
+                Cause::SpikeTrap,
+                game,
+                world,
+            );
+        }
+        TrapKind::Alarm => {
+            game.messages
+                .add("A bell rings out across the level!", YELLOW);
+            game.make_noise(x, y, global::ALARM_NOISE_RADIUS);
+        }
+        TrapKind::Flash => {
+            game.messages
+                .add("A searing flash of light blinds you!", YELLOW);
+            status::add_effect(
+                world.player,
+                Effect::Blind,
+                global::FLASH_BLIND_TURNS,
+                world,
+            );
+        }
+    }
+}
+
+/// Look around the player for hidden traps. Always takes a turn.
+pub fn player_search(game: &mut map::Game, world: &mut World) {
+    let (x, y) = world.pos(world.player);
+    let mut found = false;
+    for (tile_x, tile_y) in game.map.neighbours(x, y).collect::<Vec<_>>() {
+        if let Some(trap) = trap_at(tile_x, tile_y, world) {
+            if is_hidden(trap, world) && game.rng.gen::<f32>() < global::SEARCH_CHANCE {
+                world.traps[trap].hidden = false;
+                game.messages
+                    .add(format!("You find a {}!", world.name(trap)), YELLOW);
+                found = true;
+            }
+        }
+    }
+    if !found {
+        game.messages
+            .add("You search the area but find nothing.", WHITE);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::death;
+use crate::global;
+use crate::map::Game;
+use crate::utils;
+use crate::world::World;
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use tcod::colors;
+use tcod::console::*;
+
+/// One finished game in the high-score table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    pub score: i32,
+    pub name: String,
+    pub depth: i32,
+    pub kills: i32,
+    pub xp: i32,
+    pub turns: i32,
+    /// when the game ended, as YYYY-MM-DD
+    pub date: String,
+    /// e.g. "killed by a troll on level 3"
+    pub cause: String,
+}
+
+impl Score {
+    /// the entry for a game that has just ended
+    pub fn of(game: &Game, world: &World) -> Self {
+        let xp = world.fighters.get(world.player).map_or(0, |f| f.xp);
+        Score {
+            score: xp + game.depth * global::SCORE_PER_DEPTH,
+            name: world.name(world.player).to_string(),
+            depth: game.depth,
+            kills: game.stats.total_kills(),
+            xp,
+            turns: game.turn,
+            date: utils::today(),
+            cause: death::death_summary(game),
+        }
+    }
+
+    /// one line of the high-score file, with the fields separated by tabs
+    fn to_line(&self) -> String {
+        // a tab or a newline in a name would throw the whole file out
+        let clean = |text: &str| text.replace(['\t', '\n'], " ");
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.score,
+            clean(&self.name),
+            self.depth,
+            self.kills,
+            self.xp,
+            self.turns,
+            self.date,
+            clean(&self.cause)
+        )
+    }
+
+    /// read back a line written by `to_line`; `None` if it's been mangled
+    fn parse(line: &str) -> Option<Score> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() != 8 {
+            return None;
+        }
+        Some(Score {
+            score: fields[0].parse().ok()?,
+            name: fields[1].to_string(),
+            depth: fields[2].parse().ok()?,
+            kills: fields[3].parse().ok()?,
+            xp: fields[4].parse().ok()?,
+            turns: fields[5].parse().ok()?,
+            date: fields[6].to_string(),
+            cause: fields[7].to_string(),
+        })
+    }
+}
+
+/// Read the high-score table, best first. A missing file is just an empty
+/// table, but any other trouble reading it is an error: treating it as empty
+/// would have the next save wipe out every score in it.
+pub fn load() -> io::Result<Vec<Score>> {
+    match fs::read(global::HIGH_SCORE_FILE) {
+        // a stray bad byte only spoils the line it's on
+        Ok(bytes) => Ok(parse_table(&String::from_utf8_lossy(&bytes))),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(error),
+    }
+}
+
+/// the scores in the text of a high-score file, best first. Any line that
+/// can't be made sense of is skipped rather than losing every other score
+/// along with it
+fn parse_table(text: &str) -> Vec<Score> {
+    let mut scores = text.lines().filter_map(Score::parse).collect::<Vec<_>>();
+    scores.sort_by_key(|score| Reverse(score.score));
+    scores.truncate(global::MAX_HIGH_SCORES);
+    scores
+}
+
+/// Write the table out. It goes to a temporary file first, so a crash halfway
+/// through can't leave a half-written table behind.
+pub fn save(scores: &[Score]) -> io::Result<()> {
+    let text = scores
+        .iter()
+        .map(|score| score.to_line() + "\n")
+        .collect::<String>();
+    let temporary = format!("{}.tmp", global::HIGH_SCORE_FILE);
+    fs::write(&temporary, text)?;
+    fs::rename(&temporary, global::HIGH_SCORE_FILE)
+}
+
+/// Put a new score in its place in the table, dropping whatever falls off the
+/// bottom. Returns where it ended up, if it made it in at all.
+pub fn add(scores: &mut Vec<Score>, score: Score) -> Option<usize> {
+    let place = scores
+        .iter()
+        .position(|other| score.score > other.score)
+        .unwrap_or(scores.len());
+    scores.insert(place, score);
+    scores.truncate(global::MAX_HIGH_SCORES);
+    if place < scores.len() {
+        Some(place)
+    } else {
+        None
+    }
+}
+
+/// Show the table until a key is pressed, picking out one entry (the game
+/// that just ended) if there is one, with a note underneath.
+pub fn show_high_scores(root: &mut Root, scores: &[Score], highlight: Option<usize>, note: &str) {
+    root.set_default_background(colors::BLACK);
+    root.clear();
+    let center_x = root.width() / 2;
+    root.set_default_foreground(colors::LIGHT_YELLOW);
+    root.print_ex(
+        center_x,
+        2,
+        BackgroundFlag::None,
+        TextAlignment::Center,
+        "HIGH SCORES",
+    );
+    if scores.is_empty() {
+        root.set_default_foreground(colors::LIGHT_GREY);
+        root.print_ex(
+            center_x,
+            5,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            "No games finished yet.",
+        );
+    }
+    for (index, score) in scores.iter().enumerate() {
+        let y = 5 + index as i32 * 2;
+        let color = if highlight == Some(index) {
+            colors::YELLOW
+        } else {
+            colors::WHITE
+        };
+        root.set_default_foreground(color);
+        root.print_ex(
+            2,
+            y,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            format!(
+                "{:>2}. {:>6}  {} ({})",
+                index + 1,
+                score.score,
+                score.name,
+                score.date
+            ),
+        );
+        root.set_default_foreground(colors::LIGHT_GREY);
+        root.print_ex(
+            14,
+            y + 1,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            format!(
+                "{}; {} kills, {} XP, {} turns",
+                score.cause, score.kills, score.xp, score.turns
+            ),
+        );
+    }
+    root.set_default_foreground(colors::LIGHT_GREY);
+    let bottom = root.height() - 2;
+    root.print_ex(
+        center_x,
+        bottom,
+        BackgroundFlag::None,
+        TextAlignment::Center,
+        note,
+    );
+    root.flush();
+    root.wait_for_keypress(true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(score: i32, name: &str) -> Score {
+        Score {
+            score,
+            name: name.to_string(),
+            depth: 3,
+            kills: 12,
+            xp: 150,
+            turns: 2048,
+            date: "2024-05-01".to_string(),
+            cause: "killed by a troll on level 3".to_string(),
+        }
+    }
+
+    #[test]
+    fn line_round_trip() {
+        let original = score(450, "Ada");
+        assert_eq!(Score::parse(&original.to_line()), Some(original));
+    }
+
+    #[test]
+    fn tabs_and_newlines_in_a_name_stay_on_one_line() {
+        let line = score(450, "Ada\tthe\nBold").to_line();
+        assert!(!line.contains('\n'));
+        assert_eq!(Score::parse(&line).unwrap().name, "Ada the Bold");
+    }
+
+    #[test]
+    fn mangled_lines_are_skipped() {
+        assert_eq!(Score::parse(""), None);
+        assert_eq!(Score::parse("not a score"), None);
+        assert_eq!(Score::parse("450\tAda\t3\t12"), None);
+        assert_eq!(
+            Score::parse("lots\tAda\t3\t12\t150\t2048\t2024-05-01\tdied"),
+            None
+        );
+
+        let text = [
+            score(100, "Bob").to_line(),
+            "garbage".to_string(),
+            score(300, "Ada").to_line(),
+            "1\t2\t3".to_string(),
+            String::from_utf8_lossy(b"\xff\xfe\t\x00").into_owned(),
+        ]
+        .join("\n");
+        let scores = parse_table(&text);
+        assert_eq!(scores, vec![score(300, "Ada"), score(100, "Bob")]);
+    }
+}
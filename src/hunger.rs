@@ -0,0 +1,120 @@
+use crate::death::Cause;
+use crate::global;
+use crate::map;
+use crate::objects::{self, Fighter};
+use crate::status;
+use crate::world::{Entity, World};
+use rand::Rng;
+use tcod::colors::{self, Color};
+
+/// How much food something has in it, counted down a point every turn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hunger {
+    pub nutrition: i32,
+}
+
+/// How badly something needs to eat, from fine to about to keel over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HungerLevel {
+    Fed,
+    Hungry,
+    Weak,
+    Fainting,
+}
+
+impl HungerLevel {
+    pub fn of(nutrition: i32) -> Self {
+        if nutrition < global::FAINTING_NUTRITION {
+            HungerLevel::Fainting
+        } else if nutrition < global::WEAK_NUTRITION {
+            HungerLevel::Weak
+        } else if nutrition < global::HUNGRY_NUTRITION {
+            HungerLevel::Hungry
+        } else {
+            HungerLevel::Fed
+        }
+    }
+
+    /// what the panel shows; nothing at all while there's no need to eat
+    pub fn label(self) -> Option<(&'static str, Color)> {
+        match self {
+            HungerLevel::Fed => None,
+            HungerLevel::Hungry => Some(("Hungry", colors::YELLOW)),
+            HungerLevel::Weak => Some(("Weak", colors::ORANGE)),
+            HungerLevel::Fainting => Some(("Fainting", colors::RED)),
+        }
+    }
+
+    /// what the player is told on getting this hungry
+    fn warning(self) -> Option<&'static str> {
+        match self {
+            HungerLevel::Fed => None,
+            HungerLevel::Hungry => Some("You are getting hungry."),
+            HungerLevel::Weak => Some("You feel weak with hunger."),
+            HungerLevel::Fainting => Some("You are fainting from hunger!"),
+        }
+    }
+}
+
+pub fn hunger_level(entity: Entity, world: &World) -> HungerLevel {
+    world
+        .hunger
+        .get(entity)
+        .map_or(HungerLevel::Fed, |hunger| HungerLevel::of(hunger.nutrition))
+}
+
+/// Fill something up, but only so far: the rest goes to waste.
+pub fn feed(entity: Entity, nutrition: i32, world: &mut World) {
+    if let Some(hunger) = world.hunger.get_mut(entity) {
+        hunger.nutrition = (hunger.nutrition + nutrition).min(global::MAX_NUTRITION);
+    }
+}
+
+/// The stats something actually fights with: weak with hunger, it hits
+/// softer and misses more often.
+pub fn weakened(entity: Entity, fighter: Fighter, world: &World) -> Fighter {
+    if hunger_level(entity, world) >= HungerLevel::Weak {
+        Fighter {
+            power: fighter.power - global::WEAK_POWER_PENALTY,
+            accuracy: fighter.accuracy - global::WEAK_ACCURACY_PENALTY,
+            ..fighter
+        }
+    } else {
+        fighter
+    }
+}
+
+/// Burn a turn's worth of food for everything that gets hungry. Warns the
+/// player as they get hungrier, makes them faint now and then once they're
+/// close to starving, and kills them when there's nothing left.
+pub fn tick_hunger(game: &mut map::Game, world: &mut World) {
+    for entity in world.hunger.entities() {
+        let before = hunger_level(entity, world);
+        let hunger = &mut world.hunger[entity];
+        hunger.nutrition -= 1;
+        let nutrition = hunger.nutrition;
+        let after = HungerLevel::of(nutrition);
+        let is_player = entity == world.player;
+
+        if nutrition <= 0 {
+            if is_player {
+                game.messages.add("You starve to death!", colors::RED);
+            }
+            let hp = world.fighters.get(entity).map_or(0, |f| f.hp);
+            objects::take_damage(entity, hp, Cause::Starvation, game, world);
+            continue;
+        }
+        if is_player && after > before {
+            if let (Some(warning), Some((_, color))) = (after.warning(), after.label()) {
+                game.messages.add(warning, color);
+            }
+        }
+        if after == HungerLevel::Fainting && game.rng.gen::<f32>() < global::FAINT_CHANCE {
+            if is_player {
+                game.messages
+                    .add("You faint from lack of food.", colors::RED);
+            }
+            status::delay(entity, global::FAINT_TURNS, world);
+        }
+    }
+}
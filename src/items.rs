@@ -0,0 +1,186 @@
+use crate::equipment::{self, Equipment, Slot};
+use crate::global;
+use crate::hunger;
+use crate::magic::{self, Spell, Targeting};
+use crate::map;
+use crate::objects::Object;
+use crate::status::{self, Effect};
+use crate::world::{Entity, World};
+use tcod::colors::*;
+
+/// Something that can be picked up and carried around.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Item {
+    /// a day's worth of dried food
+    Ration,
+    /// closes wounds in a moment
+    HealingPotion,
+    Sword,
+    Shield,
+    Dagger,
+    /// calls down a lightning bolt, once
+    LightningScroll,
+    /// shows the layout of the whole level
+    MagicMappingScroll,
+    /// lights up the reader, so they can see far and wide for a while
+    LightScroll,
+    /// teaches whoever reads it a spell
+    Book(Spell),
+}
+
+impl Item {
+    /// the spell that using the item up casts, if it holds one
+    pub fn spell(self) -> Option<Spell> {
+        match self {
+            Item::HealingPotion => Some(Spell::Heal),
+            Item::LightningScroll => Some(Spell::Bolt),
+            _ => None,
+        }
+    }
+}
+
+/// Put a new item of the given kind on the map, looking the part and, if
+/// it's something to wear or wield, ready to be equipped.
+pub fn spawn_item(item: Item, x: i32, y: i32, world: &mut World) -> Entity {
+    let (character, name, color) = match item {
+        Item::Ration => ('%', "ration of food".to_string(), global::RATION_COLOR),
+        Item::HealingPotion => ('!', "healing potion".to_string(), global::POTION_COLOR),
+        Item::Sword => ('/', "sword".to_string(), global::SWORD_COLOR),
+        Item::Shield => ('[', "shield".to_string(), global::SHIELD_COLOR),
+        Item::Dagger => ('-', "dagger".to_string(), global::DAGGER_COLOR),
+        Item::LightningScroll => (
+            '#',
+            "scroll of lightning bolt".to_string(),
+            global::SCROLL_COLOR,
+        ),
+        Item::MagicMappingScroll => (
+            '#',
+            "scroll of magic mapping".to_string(),
+            global::SCROLL_COLOR,
+        ),
+        Item::LightScroll => ('#', "scroll of light".to_string(), global::SCROLL_COLOR),
+        Item::Book(spell) => ('+', format!("book of {}", spell.name()), global::BOOK_COLOR),
+    };
+    // where it's worn, and how much attack and defense it adds
+    let equipment = match item {
+        Item::Sword => Some((Slot::RightHand, 3, 0)),
+        Item::Dagger => Some((Slot::RightHand, 1, 0)),
+        Item::Shield => Some((Slot::LeftHand, 0, 1)),
+        _ => None,
+    };
+    let entity = world.spawn(Object::new(x, y, character, &name, color, false));
+    world.items.insert(entity, item);
+    if let Some((slot, power_bonus, defense_bonus)) = equipment {
+        world.equipment.insert(
+            entity,
+            Equipment {
+                slot,
+                equipped: false,
+                power_bonus,
+                defense_bonus,
+                max_hp_bonus: 0,
+            },
+        );
+    }
+    entity
+}
+
+/// the item lying at the given spot, if any
+pub fn item_at(x: i32, y: i32, world: &World) -> Option<Entity> {
+    world
+        .at(x, y)
+        .iter()
+        .rev()
+        .copied()
+        .find(|&entity| world.items.contains(entity))
+}
+
+/// Pick up whatever item is under the player. Returns `true` if that took a turn.
+pub fn player_pick_up(game: &mut map::Game, world: &mut World) -> bool {
+    let (x, y) = world.pos(world.player);
+    let item = match item_at(x, y, world) {
+        Some(item) => item,
+        None => {
+            game.messages.add("There's nothing here to pick up.", WHITE);
+            return false;
+        }
+    };
+    if world.inventory.len() >= global::INVENTORY_SIZE {
+        game.messages.add(
+            format!(
+                "Your inventory is full, cannot pick up {}.",
+                world.name(item)
+            ),
+            RED,
+        );
+        return false;
+    }
+    world.pick_up(item);
+    game.messages
+        .add(format!("You picked up a {}!", world.name(item)), GREEN);
+    true
+}
+
+/// Put down an item the player is carrying, where they stand.
+pub fn player_drop(item: Entity, game: &mut map::Game, world: &mut World) {
+    equipment::dequip(item, game, world);
+    let (x, y) = world.pos(world.player);
+    world.put_down(item, x, y);
+    game.messages
+        .add(format!("You dropped a {}.", world.name(item)), YELLOW);
+}
+
+/// How the item picks what it works on, when it's used.
+pub fn targeting(item: Entity, world: &World) -> Targeting {
+    world.items[item]
+        .spell()
+        .map_or(Targeting::Caster, Spell::targeting)
+}
+
+/// Use an item the player is carrying at the given tile, which uses it up, or
+/// put it on or take it off if it's equipment. Returns `true` if that took a
+/// turn; using something pointless is called off instead.
+pub fn player_use(item: Entity, x: i32, y: i32, game: &mut map::Game, world: &mut World) -> bool {
+    let kind = world.items[item];
+    match kind {
+        Item::Ration => {
+            game.messages.add("That really hit the spot!", GREEN);
+            hunger::feed(world.player, global::RATION_NUTRITION, world);
+        }
+        Item::HealingPotion | Item::LightningScroll => {
+            // the same magic as casting the spell, just without the mana
+            let spell = kind.spell().unwrap();
+            if !magic::invoke(spell, world.player, x, y, game, world) {
+                return false;
+            }
+            if kind == Item::HealingPotion {
+                game.stats.potions_used += 1;
+            }
+        }
+        Item::MagicMappingScroll => {
+            game.messages
+                .add("The layout of the level is revealed!", LIGHT_GREY);
+            // what the scroll shows counts as explored, same as what's seen
+            game.stats.tiles_explored += map::magic_mapping(&mut game.map);
+        }
+        Item::LightScroll => {
+            game.messages
+                .add("A brilliant light shines out around you!", LIGHT_YELLOW);
+            status::add_effect(world.player, Effect::Radiant, global::RADIANT_TURNS, world);
+            game.fov.radius = global::RADIANT_SIGHT_RADIUS;
+        }
+        Item::Book(spell) => {
+            game.messages
+                .add(format!("You read the {}.", world.name(item)), WHITE);
+            if !magic::learn(spell, game, world) {
+                return false;
+            }
+        }
+        Item::Sword | Item::Shield | Item::Dagger => {
+            equipment::toggle(item, game, world);
+            return true;
+        }
+    }
+    world.despawn(item);
+    true
+}
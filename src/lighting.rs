@@ -0,0 +1,89 @@
+use crate::global;
+use crate::grid::Grid;
+use crate::map;
+use crate::sight;
+use crate::status::{self, Effect};
+use crate::world::World;
+use tcod::colors::*;
+use tcod::map::Map as FovMap;
+
+/// Something giving off light: the player's torch, a brazier, a monster's
+/// glow. Fuel is counted down every turn; `None` means it never runs out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub radius: i32,
+    /// how bright it is right at the source, from 0 to 1
+    pub intensity: f32,
+    pub fuel: Option<i32>,
+}
+
+/// Work out how brightly lit every tile is: the ambient light everywhere, plus
+/// whatever each light source reaches, fading with distance.
+pub fn update_light(game: &mut map::Game, world: &World) {
+    let mut light = Grid::new(game.map.width(), game.map.height(), global::AMBIENT_LIGHT);
+    for (entity, &source) in world.lights.iter() {
+        // something radiant lights up far more than its usual light would
+        let source = if status::has_effect(entity, Effect::Radiant, world) {
+            Light {
+                radius: source.radius.max(global::RADIANT_LIGHT_RADIUS),
+                intensity: 1.0,
+                ..source
+            }
+        } else {
+            source
+        };
+        let (source_x, source_y) = world.pos(entity);
+        for y in (source_y - source.radius)..=(source_y + source.radius) {
+            for x in (source_x - source.radius)..=(source_x + source.radius) {
+                let distance = (((x - source_x).pow(2) + (y - source_y).pow(2)) as f32).sqrt();
+                if distance > source.radius as f32 || !game.map.in_bounds(x, y) {
+                    continue;
+                }
+                if !sight::line_of_sight(&game.map, (source_x, source_y), (x, y)) {
+                    continue;
+                }
+                let falloff = 1.0 - distance / (source.radius as f32 + 1.0);
+                let level = &mut light[(x, y)];
+                *level = (*level + source.intensity * falloff).min(1.0);
+            }
+        }
+    }
+    game.light = light;
+}
+
+/// whether there's enough light on a tile to make out what's there
+pub fn is_lit(x: i32, y: i32, game: &map::Game) -> bool {
+    game.light
+        .get(x, y)
+        .map_or(false, |&level| level >= global::MIN_VISIBLE_LIGHT)
+}
+
+/// whether the player can see a tile: it has to be in their field of view,
+/// and lit well enough
+pub fn is_visible(x: i32, y: i32, fov: &FovMap, game: &map::Game) -> bool {
+    fov.is_in_fov(x, y) && is_lit(x, y, game)
+}
+
+/// Use up a turn's worth of fuel from every light that burns any. A torch
+/// that runs out doesn't go dark completely, but smoulders on as embers.
+pub fn burn_fuel(game: &mut map::Game, world: &mut World) {
+    let player = world.player;
+    for (entity, light) in world.lights.iter_mut() {
+        let fuel = match light.fuel.as_mut() {
+            Some(fuel) => fuel,
+            None => continue,
+        };
+        *fuel -= 1;
+        if *fuel <= 0 {
+            light.radius = global::EMBER_RADIUS;
+            light.intensity = global::EMBER_INTENSITY;
+            light.fuel = None;
+            if entity == player {
+                game.messages
+                    .add("Your torch burns out, leaving only embers!", ORANGE);
+            }
+        } else if *fuel == global::TORCH_LOW_FUEL && entity == player {
+            game.messages.add("Your torch begins to flicker.", YELLOW);
+        }
+    }
+}
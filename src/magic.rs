@@ -0,0 +1,310 @@
+use crate::death::Cause;
+use crate::equipment;
+use crate::global;
+use crate::hazards;
+use crate::map;
+use crate::objects;
+use crate::status::{self, Effect};
+use crate::world::{Entity, World};
+use tcod::colors::*;
+
+/// Something magic that can be made to happen, either by casting it from the
+/// spellbook or by using up an item that holds it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spell {
+    /// strikes a monster with lightning
+    Bolt,
+    /// closes the caster's wounds
+    Heal,
+    /// jumps the caster to a spot they can see
+    Blink,
+    /// makes a monster lose every other turn for a while
+    Slow,
+}
+
+pub const SPELLS: [Spell; 4] = [Spell::Bolt, Spell::Heal, Spell::Blink, Spell::Slow];
+
+/// How a spell picks what it works on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Targeting {
+    /// whoever casts it
+    Caster,
+    /// a tile in view, no further away than the range
+    Tile { range: f32 },
+}
+
+impl Spell {
+    pub fn name(self) -> &'static str {
+        match self {
+            Spell::Bolt => "bolt",
+            Spell::Heal => "heal",
+            Spell::Blink => "blink",
+            Spell::Slow => "slow",
+        }
+    }
+
+    /// mana it takes to cast
+    pub fn cost(self) -> i32 {
+        match self {
+            Spell::Bolt => global::BOLT_COST,
+            Spell::Heal => global::HEAL_COST,
+            Spell::Blink => global::BLINK_COST,
+            Spell::Slow => global::SLOW_COST,
+        }
+    }
+
+    /// turns to wait after casting it before it can be cast again
+    pub fn cooldown(self) -> i32 {
+        match self {
+            Spell::Bolt => global::BOLT_COOLDOWN,
+            Spell::Heal => global::HEAL_COOLDOWN,
+            Spell::Blink => global::BLINK_COOLDOWN,
+            Spell::Slow => global::SLOW_COOLDOWN,
+        }
+    }
+
+    pub fn targeting(self) -> Targeting {
+        match self {
+            Spell::Bolt => Targeting::Tile {
+                range: global::BOLT_RANGE,
+            },
+            Spell::Heal => Targeting::Caster,
+            Spell::Blink => Targeting::Tile {
+                range: global::BLINK_RANGE,
+            },
+            Spell::Slow => Targeting::Tile {
+                range: global::SLOW_RANGE,
+            },
+        }
+    }
+}
+
+/// Magical energy to cast spells with. It comes back slowly by itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mana {
+    pub mana: i32,
+    pub max_mana: i32,
+    /// mana regained so far that doesn't add up to a whole point yet
+    pub progress: f32,
+}
+
+impl Mana {
+    pub fn new(max_mana: i32) -> Self {
+        Mana {
+            mana: max_mana,
+            max_mana,
+            progress: 0.0,
+        }
+    }
+}
+
+/// The spells an entity has learned, each with the turns left before it can
+/// be cast again.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Spellbook {
+    pub spells: Vec<(Spell, i32)>,
+}
+
+impl Spellbook {
+    pub fn new(spells: &[Spell]) -> Self {
+        Spellbook {
+            spells: spells.iter().map(|&spell| (spell, 0)).collect(),
+        }
+    }
+
+    pub fn knows(&self, spell: Spell) -> bool {
+        self.spells.iter().any(|&(known, _)| known == spell)
+    }
+
+    /// turns left before the spell can be cast again
+    pub fn cooldown(&self, spell: Spell) -> i32 {
+        self.spells
+            .iter()
+            .find(|&&(known, _)| known == spell)
+            .map_or(0, |&(_, turns_left)| turns_left)
+    }
+}
+
+/// Teach the player a new spell. Returns `false` if they already knew it.
+pub fn learn(spell: Spell, game: &mut map::Game, world: &mut World) -> bool {
+    let player = world.player;
+    if world
+        .spellbooks
+        .get(player)
+        .filter(|book| book.knows(spell))
+        .is_some()
+    {
+        game.messages
+            .add(format!("You already know how to {}.", spell.name()), RED);
+        return false;
+    }
+    if !world.spellbooks.contains(player) {
+        world.spellbooks.insert(player, Spellbook::default());
+    }
+    world.spellbooks[player].spells.push((spell, 0));
+    game.messages
+        .add(format!("You learn to cast {}!", spell.name()), LIGHT_BLUE);
+    true
+}
+
+/// Whether the player could cast the spell right now, telling them why not.
+pub fn can_cast(spell: Spell, game: &mut map::Game, world: &World) -> bool {
+    let player = world.player;
+    let cooldown = world
+        .spellbooks
+        .get(player)
+        .map_or(0, |b| b.cooldown(spell));
+    let mana = world.mana.get(player).map_or(0, |m| m.mana);
+    if cooldown > 0 {
+        game.messages.add(
+            format!(
+                "You can't cast {} again for {} turns.",
+                spell.name(),
+                cooldown
+            ),
+            RED,
+        );
+        false
+    } else if mana < spell.cost() {
+        game.messages.add(
+            format!("You don't have the mana to cast {}.", spell.name()),
+            RED,
+        );
+        false
+    } else {
+        true
+    }
+}
+
+/// Cast a spell from the player's spellbook at the given tile, paying its
+/// cost if it works. Returns `true` if it was cast.
+pub fn player_cast(spell: Spell, x: i32, y: i32, game: &mut map::Game, world: &mut World) -> bool {
+    if !invoke(spell, world.player, x, y, game, world) {
+        return false;
+    }
+    let player = world.player;
+    if let Some(mana) = world.mana.get_mut(player) {
+        mana.mana -= spell.cost();
+    }
+    if let Some(book) = world.spellbooks.get_mut(player) {
+        for (known, turns_left) in book.spells.iter_mut() {
+            if *known == spell {
+                *turns_left = spell.cooldown();
+            }
+        }
+    }
+    true
+}
+
+/// Make a spell happen, wherever the magic comes from. Returns `false` if it
+/// couldn't take effect, so that nothing is spent on it.
+pub fn invoke(
+    spell: Spell,
+    caster: Entity,
+    x: i32,
+    y: i32,
+    game: &mut map::Game,
+    world: &mut World,
+) -> bool {
+    match spell {
+        Spell::Bolt => bolt(caster, x, y, game, world),
+        Spell::Heal => heal(caster, game, world),
+        Spell::Blink => blink(caster, x, y, game, world),
+        Spell::Slow => slow(caster, x, y, game, world),
+    }
+}
+
+/// the fighter at the given tile, other than the caster
+fn target_at(
+    caster: Entity,
+    x: i32,
+    y: i32,
+    game: &mut map::Game,
+    world: &World,
+) -> Option<Entity> {
+    let target = world
+        .at(x, y)
+        .iter()
+        .copied()
+        .find(|&entity| entity != caster && world.is_alive(entity));
+    if target.is_none() {
+        game.messages.add("There's no one there.", WHITE);
+    }
+    target
+}
+
+fn bolt(caster: Entity, x: i32, y: i32, game: &mut map::Game, world: &mut World) -> bool {
+    let target = match target_at(caster, x, y, game, world) {
+        Some(target) => target,
+        None => return false,
+    };
+    let name = world.name(target).to_string();
+    let xp = world.fighters[target].xp;
+    game.messages.add(
+        format!(
+            "A lightning bolt strikes the {} with a loud thunder! The damage is {} hit points.",
+            name,
+            global::BOLT_DAMAGE
+        ),
+        LIGHT_BLUE,
+    );
+    let cause = Cause::Monster(world.name(caster).to_string());
+    objects::take_damage(target, global::BOLT_DAMAGE, cause, game, world);
+    if caster == world.player && !world.is_alive(target) {
+        objects::credit_kill(&name, xp, game, world);
+    }
+    true
+}
+
+fn heal(caster: Entity, game: &mut map::Game, world: &mut World) -> bool {
+    let hp = world.fighters.get(caster).map_or(0, |f| f.hp);
+    if hp >= equipment::max_hp(caster, world) {
+        game.messages.add("You are already at full health.", RED);
+        return false;
+    }
+    game.messages
+        .add("Your wounds start to feel better!", LIGHT_VIOLET);
+    objects::heal(caster, global::HEAL_AMOUNT, world);
+    true
+}
+
+fn blink(caster: Entity, x: i32, y: i32, game: &mut map::Game, world: &mut World) -> bool {
+    if map::is_blocked_for(caster, x, y, game, world) {
+        game.messages.add("There's no room to land there.", WHITE);
+        return false;
+    }
+    game.messages
+        .add("The world folds, and you are somewhere else.", LIGHT_BLUE);
+    let from = game.map[world.pos(caster)].terrain;
+    world.set_pos(caster, x, y);
+    hazards::enter_tile(caster, from, game, world);
+    true
+}
+
+fn slow(caster: Entity, x: i32, y: i32, game: &mut map::Game, world: &mut World) -> bool {
+    let target = match target_at(caster, x, y, game, world) {
+        Some(target) => target,
+        None => return false,
+    };
+    game.messages.add(
+        format!("The {} starts moving sluggishly.", world.name(target)),
+        LIGHT_BLUE,
+    );
+    status::add_effect(target, Effect::Slow, global::SLOW_TURNS, world);
+    true
+}
+
+/// Bring back a turn's worth of mana and count down every spell's cooldown.
+pub fn tick_magic(world: &mut World) {
+    for (_, mana) in world.mana.iter_mut() {
+        mana.progress += global::MANA_REGENERATION;
+        let amount = mana.progress.floor();
+        mana.progress -= amount;
+        mana.mana = (mana.mana + amount as i32).min(mana.max_mana);
+    }
+    for (_, book) in world.spellbooks.iter_mut() {
+        for (_, turns_left) in book.spells.iter_mut() {
+            *turns_left = (*turns_left - 1).max(0);
+        }
+    }
+}
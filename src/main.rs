@@ -1,10 +1,39 @@
+use rand::{SeedableRng, StdRng};
 use tcod::colors;
 use tcod::console::*;
+use tcod::input::{self, Event, Key, Mouse};
 use tcod::map::Map as FovMap;
+use world::World;
+mod activity;
+mod ai;
+mod character;
+mod combat;
+mod corpses;
+mod creation;
+mod death;
+mod equipment;
 mod global;
+mod grid;
+mod hazards;
+mod highscores;
+mod hunger;
+mod items;
+mod lighting;
+mod magic;
 mod map;
+mod menu;
 mod objects;
+mod overview;
+mod pathfinding;
+mod regeneration;
+mod screen;
+mod sight;
+mod spatial;
+mod stats;
+mod status;
+mod terrain;
 mod utils;
+mod world;
 use PlayerAction::*;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -18,39 +47,42 @@ struct Tcod {
     con: Offscreen,
     panel: Offscreen,
     fov: FovMap,
+    key: Key,
+    mouse: Mouse,
+    layout: screen::Layout,
+    camera: screen::Camera,
+    /// what the player is busy doing by themselves, if anything
+    activity: Option<activity::Activity>,
 }
 
-fn ai_take_turn(
-    monster_id: usize,
-    tcod: &Tcod,
-    game: &mut map::Game,
-    objects: &mut [objects::Object],
-) {
-    // a basic monster takes its turn. If you can see it, it can see you
-    let (monster_x, monster_y) = objects[monster_id].pos();
-    if tcod.fov.is_in_fov(monster_x, monster_y) {
-        if objects[monster_id].distance_to(&objects[global::PLAYER]) >= 2.0 {
-            // move towards player if far away
-            let (player_x, player_y) = objects[global::PLAYER].pos();
-            objects::move_towards(monster_id, player_x, player_y, game, objects);
-        } else if objects[global::PLAYER].fighter.map_or(false, |f| f.hp > 0) {
-            // close enough, attack! (if the player is still alive.)
-            let (monster, player) = utils::mut_two(monster_id, global::PLAYER, objects);
-            monster.attack(player, game);
-        }
-    }
-}
-
-fn handle_keys(
+/// set the player off on a task they'll carry on with by themselves
+fn start_activity(
+    task: activity::Task,
     tcod: &mut Tcod,
     game: &mut map::Game,
-    objects: &mut [objects::Object],
+    world: &World,
 ) -> PlayerAction {
-    use tcod::input::Key;
+    tcod.activity = activity::Activity::start(task, &tcod.fov, game, world);
+    DidntTakeTurn
+}
+
+fn handle_keys(tcod: &mut Tcod, game: &mut map::Game, world: &mut World) -> PlayerAction {
+    use activity::Task::*;
     use tcod::input::KeyCode::*;
 
-    let key = tcod.root.wait_for_keypress(true);
-    let player_alive = &objects[global::PLAYER].alive;
+    let key = tcod.key;
+    let player_alive = world.is_alive(world.player);
+
+    // clicking on a tile the player has explored walks them over there
+    if tcod.mouse.lbutton_pressed && player_alive {
+        tcod.mouse.lbutton_pressed = false;
+        let to = tile_under_mouse(tcod.mouse, &tcod.camera, game)
+            .filter(|&(x, y)| game.map[(x, y)].explored);
+        if let Some(to) = to {
+            return start_activity(Travel { to }, tcod, game, world);
+        }
+    }
+
     let ret = match (key, key.text(), player_alive) {
         (
             Key {
@@ -68,23 +100,195 @@ fn handle_keys(
         }
         (Key { code: Escape, .. }, _, _) => return Exit, // exit game
 
+        // shift+direction: run until something comes up
+        (
+            Key {
+                code: Up,
+                shift: true,
+                ..
+            },
+            _,
+            true,
+        ) => start_activity(
+            Run {
+                dx: 0,
+                dy: -1,
+                open: None,
+            },
+            tcod,
+            game,
+            world,
+        ),
+        (
+            Key {
+                code: Down,
+                shift: true,
+                ..
+            },
+            _,
+            true,
+        ) => start_activity(
+            Run {
+                dx: 0,
+                dy: 1,
+                open: None,
+            },
+            tcod,
+            game,
+            world,
+        ),
+        (
+            Key {
+                code: Left,
+                shift: true,
+                ..
+            },
+            _,
+            true,
+        ) => start_activity(
+            Run {
+                dx: -1,
+                dy: 0,
+                open: None,
+            },
+            tcod,
+            game,
+            world,
+        ),
+        (
+            Key {
+                code: Right,
+                shift: true,
+                ..
+            },
+            _,
+            true,
+        ) => start_activity(
+            Run {
+                dx: 1,
+                dy: 0,
+                open: None,
+            },
+            tcod,
+            game,
+            world,
+        ),
+
         // movement keys
         (Key { code: Up, .. }, _, true) => {
-            objects::player_move_or_attack(0, -1, game, objects);
+            objects::player_move_or_attack(0, -1, game, world);
             TookTurn
         }
         (Key { code: Down, .. }, _, true) => {
-            objects::player_move_or_attack(0, 1, game, objects);
+            objects::player_move_or_attack(0, 1, game, world);
             TookTurn
         }
         (Key { code: Left, .. }, _, true) => {
-            objects::player_move_or_attack(-1, 0, game, objects);
+            objects::player_move_or_attack(-1, 0, game, world);
             TookTurn
         }
         (Key { code: Right, .. }, _, true) => {
-            objects::player_move_or_attack(1, 0, game, objects);
+            objects::player_move_or_attack(1, 0, game, world);
             TookTurn
         }
+        (Key { code: Text, .. }, "e", true) => {
+            // eat whatever lies underfoot
+            if corpses::player_eat(game, world) {
+                TookTurn
+            } else {
+                DidntTakeTurn
+            }
+        }
+        (Key { code: Text, .. }, "s", true) => {
+            // search for hidden traps nearby
+            hazards::player_search(game, world);
+            TookTurn
+        }
+        (Key { code: F2, .. }, _, _) => {
+            // debug: try out the next FOV algorithm
+            game.fov.next_algorithm();
+            game.messages.add(
+                format!("FOV algorithm: {:?}", game.fov.algorithm),
+                colors::LIGHT_GREY,
+            );
+            DidntTakeTurn
+        }
+        (Key { code: Text, .. }, "m", _) => {
+            // look at the whole level at once
+            overview::show_overview(&mut tcod.root, &tcod.layout, game, world);
+            DidntTakeTurn
+        }
+        (Key { code: Text, .. }, "o", true) => {
+            // explore automatically until something comes up
+            start_activity(Explore, tcod, game, world)
+        }
+        (Key { code: Text, .. }, "g", true) => {
+            // pick up an item
+            if items::player_pick_up(game, world) {
+                TookTurn
+            } else {
+                DidntTakeTurn
+            }
+        }
+        (Key { code: Text, .. }, "i", true) => {
+            // show the inventory: if an item is selected, use it
+            let item = menu::inventory_menu(
+                world,
+                "Press the key next to an item to use it, or any other to cancel.\n",
+                &mut tcod.root,
+            );
+            let item = match item {
+                Some(item) => item,
+                None => return DidntTakeTurn,
+            };
+            match choose_target(items::targeting(item, world), tcod, game, world) {
+                Some((x, y)) if items::player_use(item, x, y, game, world) => TookTurn,
+                _ => DidntTakeTurn,
+            }
+        }
+        (Key { code: Text, .. }, "d", true) => {
+            // show the inventory; if an item is selected, drop it
+            let item = menu::inventory_menu(
+                world,
+                "Press the key next to an item to drop it, or any other to cancel.\n",
+                &mut tcod.root,
+            );
+            match item {
+                Some(item) => {
+                    items::player_drop(item, game, world);
+                    TookTurn
+                }
+                None => DidntTakeTurn,
+            }
+        }
+        (Key { code: Text, .. }, "r", true) => {
+            // rest until healed, or something comes up
+            start_activity(Rest, tcod, game, world)
+        }
+        (Key { code: Text, .. }, "c", _) => {
+            // show the character screen
+            character::show_character_screen(&mut tcod.root, game, world);
+            DidntTakeTurn
+        }
+        (Key { code: Text, .. }, "z", true) => {
+            // cast a spell from the spellbook
+            let spell = match spell_menu(&mut tcod.root, world) {
+                Some(spell) if magic::can_cast(spell, game, world) => spell,
+                _ => return DidntTakeTurn,
+            };
+            match choose_target(spell.targeting(), tcod, game, world) {
+                Some((x, y)) if magic::player_cast(spell, x, y, game, world) => TookTurn,
+                _ => DidntTakeTurn,
+            }
+        }
+        (Key { code: Text, .. }, "b", true) => {
+            // butcher the corpse underfoot
+            if corpses::player_butcher(game, world) {
+                TookTurn
+            } else {
+                DidntTakeTurn
+            }
+        }
 
         _ => DidntTakeTurn,
     };
@@ -125,40 +329,228 @@ fn render_bar(
     );
 }
 
-fn render_all(tcod: &mut Tcod, game: &mut map::Game, objects: &[objects::Object]) {
-    let mut to_draw: Vec<_> = objects
+/// the map tile under the mouse, if the mouse is over the map at all; used
+/// both for looking around and for picking targets
+fn tile_under_mouse(mouse: Mouse, camera: &screen::Camera, game: &map::Game) -> Option<(i32, i32)> {
+    let (screen_x, screen_y) = (mouse.cx as i32, mouse.cy as i32);
+    if screen_x >= camera.width || screen_y >= camera.height {
+        return None;
+    }
+    let (x, y) = camera.map_pos(screen_x, screen_y);
+    if game.map.in_bounds(x, y) {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// Show the spells the player knows, with what they cost, and return the one
+/// they picked.
+fn spell_menu(root: &mut Root, world: &World) -> Option<magic::Spell> {
+    let spells = world
+        .spellbooks
+        .get(world.player)
+        .map_or(vec![], |book| book.spells.clone());
+    let options = if spells.is_empty() {
+        vec!["You don't know any spells.".to_string()]
+    } else {
+        spells
+            .iter()
+            .map(|&(spell, turns_left)| {
+                if turns_left > 0 {
+                    format!(
+                        "{} ({} mana, ready in {})",
+                        spell.name(),
+                        spell.cost(),
+                        turns_left
+                    )
+                } else {
+                    format!("{} ({} mana)", spell.name(), spell.cost())
+                }
+            })
+            .collect()
+    };
+    let index = menu::menu(
+        "Press the key next to a spell to cast it, or any other to cancel.\n",
+        &options,
+        global::SPELL_MENU_WIDTH,
+        root,
+    );
+    index.and_then(|index| spells.get(index).map(|&(spell, _)| spell))
+}
+
+/// Find out where the player wants a spell or item to go: their own tile if
+/// it only works on them, otherwise a tile they click on. `None` if they
+/// cancel.
+fn choose_target(
+    targeting: magic::Targeting,
+    tcod: &mut Tcod,
+    game: &mut map::Game,
+    world: &World,
+) -> Option<(i32, i32)> {
+    match targeting {
+        magic::Targeting::Caster => Some(world.pos(world.player)),
+        magic::Targeting::Tile { range } => {
+            game.messages.add(
+                "Left-click a target tile, or right-click or press Escape to cancel.",
+                colors::LIGHT_CYAN,
+            );
+            target_tile(tcod, game, world, range)
+        }
+    }
+}
+
+/// Wait for the player to click a tile in view and within range, redrawing
+/// the screen meanwhile so they can see what they're aiming at. `None` if they
+/// right-click or press Escape instead.
+fn target_tile(
+    tcod: &mut Tcod,
+    game: &mut map::Game,
+    world: &World,
+    range: f32,
+) -> Option<(i32, i32)> {
+    use tcod::input::KeyCode::Escape;
+    while !tcod.root.window_closed() {
+        // render the screen. this erases the inventory and shows the names of
+        // objects under the mouse
+        tcod.root.flush();
+        match input::check_for_event(input::MOUSE | input::KEY_PRESS) {
+            Some((_, Event::Mouse(m))) => tcod.mouse = m,
+            Some((_, Event::Key(k))) => tcod.key = k,
+            _ => tcod.key = Default::default(),
+        }
+        render_all(tcod, game, world);
+
+        // accept the target if the player clicked in view and in range
+        let target = tile_under_mouse(tcod.mouse, &tcod.camera, game).filter(|&(x, y)| {
+            let (player_x, player_y) = world.pos(world.player);
+            let distance = (((x - player_x).pow(2) + (y - player_y).pow(2)) as f32).sqrt();
+            lighting::is_visible(x, y, &tcod.fov, game) && distance <= range
+        });
+        if tcod.mouse.lbutton_pressed {
+            // don't let the same click set the player travelling afterwards
+            tcod.mouse.lbutton_pressed = false;
+            if target.is_some() {
+                return target;
+            }
+        }
+        if tcod.mouse.rbutton_pressed || tcod.key.code == Escape {
+            return None;
+        }
+    }
+    None
+}
+
+/// return a string with the names of all objects under the mouse
+fn get_names_under_mouse(
+    mouse: Mouse,
+    camera: &screen::Camera,
+    game: &map::Game,
+    world: &World,
+    fov_map: &FovMap,
+) -> String {
+    let (x, y) = match tile_under_mouse(mouse, camera, game) {
+        Some((x, y)) if lighting::is_visible(x, y, fov_map, game) => (x, y),
+        _ => return String::new(),
+    };
+
+    // create a list with the names of all objects at the mouse's coordinates
+    let mut names = world
+        .at(x, y)
         .iter()
-        .filter(|o| tcod.fov.is_in_fov(o.x, o.y))
-        .collect();
-    // sort so that non-blocking objects come first
-    to_draw.sort_by(|o1, o2| o1.blocks.cmp(&o2.blocks));
-    // draw the objects in the list
-    for object in &to_draw {
-        object.draw(&mut tcod.con);
+        .filter(|&&entity| !hazards::is_hidden(entity, world))
+        .map(|&entity| objects::description(entity, world))
+        .collect::<Vec<_>>();
+    // and what they're standing on, unless it's plain floor or wall
+    let terrain = game.map[(x, y)].terrain.info();
+    if terrain.glyph != ' ' {
+        names.push(terrain.name.to_string());
     }
-    // go through all tiles, and set their background color
-    for y in 0..global::MAP_HEIGHT {
-        for x in 0..global::MAP_WIDTH {
-            let visible = tcod.fov.is_in_fov(x, y);
-            let tile = &mut game.map[x as usize][y as usize];
-            let explored = &mut tile.explored;
-
-            let mut color = tile.color;
+
+    names.join(", ") // join the names, separated by commas
+}
+
+fn render_all(tcod: &mut Tcod, game: &mut map::Game, world: &World) {
+    let mut to_draw = vec![];
+    // go through all tiles in view, and set their background color
+    for screen_y in 0..tcod.camera.height {
+        for screen_x in 0..tcod.camera.width {
+            let (x, y) = tcod.camera.map_pos(screen_x, screen_y);
+            if !game.map.in_bounds(x, y) {
+                continue;
+            }
+            let visible = lighting::is_visible(x, y, &tcod.fov, game);
+            let light = game.light[(x, y)];
             if visible {
-                *explored = true;
+                if map::remember_tile(x, y, &mut game.map, world) {
+                    game.stats.tiles_explored += 1;
+                }
+                // only objects on tiles in view get drawn
+                to_draw.extend(
+                    world
+                        .at(x, y)
+                        .iter()
+                        .filter(|&&entity| !hazards::is_hidden(entity, world))
+                        .map(|&entity| &world.objects[entity]),
+                );
+            }
+            let tile = &game.map[(x, y)];
+            if !tile.explored {
+                continue;
+            }
+            // tiles out of view are drawn the way the player last saw them
+            let info = if visible {
+                tile.terrain.info()
             } else {
-                color = colors::lerp(tile.color, colors::BLACK, 0.4);
+                tile.memory.terrain.info()
+            };
+
+            // remembered tiles are drawn dim, and ones in view brighten up
+            // the more light falls on them
+            let mut foreground = colors::lerp(info.foreground, colors::BLACK, 0.4);
+            let mut background = colors::lerp(info.background, colors::BLACK, 0.4);
+            if visible {
+                foreground = colors::lerp(foreground, info.foreground, light);
+                background = colors::lerp(background, info.background, light);
+            }
+            tcod.con
+                .set_char_background(screen_x, screen_y, background, BackgroundFlag::Set);
+            if info.glyph != ' ' {
+                tcod.con.set_default_foreground(foreground);
+                tcod.con
+                    .put_char(screen_x, screen_y, info.glyph, BackgroundFlag::None);
             }
-            if *explored {
+            // along with anything they remember lying there
+            let remembered = tile.memory.object.filter(|_| !visible);
+            if let Some((glyph, color)) = remembered {
                 tcod.con
-                    .set_char_background(x, y, color, BackgroundFlag::Set);
+                    .set_default_foreground(colors::lerp(color, colors::BLACK, 0.4));
+                tcod.con
+                    .put_char(screen_x, screen_y, glyph, BackgroundFlag::None);
+            }
+        }
+    }
+    // a telepathic player senses the monsters around them, even out of sight
+    if status::has_effect(world.player, status::Effect::Telepathic, world) {
+        for monster in world.ais.entities() {
+            let (x, y) = world.pos(monster);
+            if world.distance(world.player, monster) <= global::TELEPATHY_RADIUS
+                && !lighting::is_visible(x, y, &tcod.fov, game)
+            {
+                to_draw.push(&world.objects[monster]);
             }
         }
     }
+    // sort so that non-blocking objects come first
+    to_draw.sort_by(|o1, o2| o1.blocks.cmp(&o2.blocks));
+    // draw the objects in the list
+    for object in &to_draw {
+        object.draw(&mut tcod.con, &tcod.camera);
+    }
     blit(
         &tcod.con,
         (0, 0),
-        (global::SCREEN_WIDTH, global::SCREEN_HEIGHT),
+        (tcod.layout.view_width, tcod.layout.view_height),
         &mut tcod.root,
         (0, 0),
         1.0,
@@ -169,8 +561,8 @@ fn render_all(tcod: &mut Tcod, game: &mut map::Game, objects: &[objects::Object]
     tcod.panel.clear();
 
     // show the player's stats
-    let hp = objects[global::PLAYER].fighter.map_or(0, |f| f.hp);
-    let max_hp = objects[global::PLAYER].fighter.map_or(0, |f| f.max_hp);
+    let hp = world.fighters.get(world.player).map_or(0, |f| f.hp);
+    let max_hp = equipment::max_hp(world.player, world);
     render_bar(
         &mut tcod.panel,
         1,
@@ -182,114 +574,349 @@ fn render_all(tcod: &mut Tcod, game: &mut map::Game, objects: &[objects::Object]
         colors::LIGHT_RED,
         colors::DARKER_RED,
     );
+    // and their mana, if they have any to speak of
+    if let Some(mana) = world.mana.get(world.player) {
+        render_bar(
+            &mut tcod.panel,
+            1,
+            2,
+            global::BAR_WIDTH,
+            "Mana",
+            mana.mana,
+            mana.max_mana,
+            colors::LIGHT_BLUE,
+            colors::DARKER_BLUE,
+        );
+    }
+    // and how hungry they are, once it starts to matter
+    if let Some((label, color)) = hunger::hunger_level(world.player, world).label() {
+        tcod.panel.set_default_foreground(color);
+        tcod.panel
+            .print_ex(1, 3, BackgroundFlag::None, TextAlignment::Left, label);
+    }
+
+    // display names of objects under the mouse
+    tcod.panel.set_default_foreground(colors::LIGHT_GREY);
+    tcod.panel.print_ex(
+        1,
+        0,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        get_names_under_mouse(tcod.mouse, &tcod.camera, game, world, &tcod.fov),
+    );
 
     // print the game messages, one line at a time
-    let mut y = global::MSG_HEIGHT as i32;
+    let layout = tcod.layout;
+    let mut y = layout.msg_height;
     for &(ref msg, color) in game.messages.iter().rev() {
         let msg_height = tcod
             .panel
-            .get_height_rect(global::MSG_X, y, global::MSG_WIDTH, 0, msg);
+            .get_height_rect(layout.msg_x, y, layout.msg_width, 0, msg);
         y -= msg_height;
         if y < 0 {
             break;
         }
         tcod.panel.set_default_foreground(color);
         tcod.panel
-            .print_rect(global::MSG_X, y, global::MSG_WIDTH, 0, msg);
+            .print_rect(layout.msg_x, y, layout.msg_width, 0, msg);
     }
     // blit the contents of `panel` to the root console
     blit(
         &tcod.panel,
         (0, 0),
-        (global::SCREEN_WIDTH, global::PANEL_HEIGHT),
+        (layout.width, global::PANEL_HEIGHT),
         &mut tcod.root,
-        (0, global::PANEL_Y),
+        (0, layout.panel_y),
         1.0,
         1.0,
     );
-    if let Some(fighter) = objects[global::PLAYER].fighter {
-        tcod.root.print_ex(
-            1,
-            global::SCREEN_HEIGHT - 2,
-            BackgroundFlag::None,
-            TextAlignment::Left,
-            format!("HP: {}/{} ", fighter.hp, fighter.max_hp),
-        );
-    }
 }
 
-fn main() {
-    let root = Root::initializer()
-        .font("arial10x10.png", FontLayout::Tcod)
-        .font_type(FontType::Greyscale)
-        .size(global::SCREEN_WIDTH, global::SCREEN_HEIGHT)
-        .title("Rust/libtcod tutorial")
-        .init();
+/// everything that happens in the world after the player has acted
+fn play_turn(game: &mut map::Game, world: &mut World) {
+    game.turn += 1;
+    game.stats.record_turn(game.depth);
+    ai::hear_noises(game, world);
+    for monster in world.ais.entities() {
+        // skip any that were killed earlier this turn, or are still wading
+        // through something
+        if world.ais.contains(monster) && !status::spend_delay(monster, world) {
+            ai::ai_take_turn(monster, game, world);
+        }
+    }
+    status::tick_statuses(game, world);
+    hunger::tick_hunger(game, world);
+    regeneration::regenerate(world);
+    magic::tick_magic(world);
+    corpses::rot_corpses(game, world);
+    lighting::burn_fuel(game, world);
+    lighting::update_light(game, world);
+}
 
-    let mut tcod = Tcod {
-        root,
-        con: Offscreen::new(global::MAP_WIDTH, global::MAP_HEIGHT),
-        panel: Offscreen::new(global::MAP_WIDTH, global::PANEL_HEIGHT),
-        fov: FovMap::new(global::MAP_WIDTH, global::MAP_HEIGHT),
-    };
-    tcod::system::set_fps(global::LIMIT_FPS);
+/// set up a fresh game: a new player, and a new level for them to explore
+fn new_game(tcod: &mut Tcod, name: &str, class: creation::Class) -> (map::Game, World) {
     // create object representing the player
-    let mut player = objects::Object::new(25, 23, '@', "player", colors::WHITE, false);
-    player.alive = true;
-    player.fighter = Some(objects::Fighter {
-        max_hp: 30,
-        hp: 30,
-        defense: 2,
-        power: 5,
-        on_death: objects::DeathCallback::Player,
-    });
-
-    // the list of objects with those two
-    let mut objects = vec![player];
+    let player = objects::Object::new(25, 23, '@', name, colors::WHITE, false);
+
+    // how big a level to make. Only the map generator goes by this: everything
+    // else is sized from the map it comes out as
+    let (width, height) = (global::MAP_WIDTH, global::MAP_HEIGHT);
+
+    // the world starts out with just the player in it
+    let mut world = World::new(width, height, player);
+    world.fighters.insert(world.player, class.fighter());
+    world.stealth.insert(
+        world.player,
+        sight::Stealth {
+            level: class.stealth(),
+        },
+    );
+    creation::give_starting_items(class, &mut world);
+    world
+        .mana
+        .insert(world.player, magic::Mana::new(class.max_mana()));
+    world
+        .spellbooks
+        .insert(world.player, magic::Spellbook::new(class.spells()));
+    world.hunger.insert(
+        world.player,
+        hunger::Hunger {
+            nutrition: global::START_NUTRITION,
+        },
+    );
+    world.regeneration.insert(
+        world.player,
+        regeneration::Regeneration::new(global::REGENERATION_RATE),
+    );
+    // and a torch to find their way with
+    world.lights.insert(
+        world.player,
+        lighting::Light {
+            radius: global::TORCH_RADIUS,
+            intensity: global::TORCH_INTENSITY,
+            fuel: Some(global::TORCH_FUEL),
+        },
+    );
+
+    // every random roll in the game comes from this RNG, so a run can be
+    // replayed by passing the same `--seed`
+    let seed = utils::seed_from_args().unwrap_or_else(utils::time_seed);
+    let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+    // generate map (at this point it's not drawn to the screen)
+    let map = map::generate(width, height, 25, 23, &mut world, &mut rng);
+    let light = grid::Grid::new(map.width(), map.height(), 0.0);
     let mut game = map::Game {
-        // generate map (at this point it's not drawn to the screen)
-        map: map::generate(global::MAP_WIDTH, global::MAP_HEIGHT, 25, 23, &mut objects),
+        map,
         messages: map::Messages::new(),
+        rng,
+        seed,
+        depth: 1,
+        turn: 0,
+        stats: Default::default(),
+        death: None,
+        noises: vec![],
+        sight: sight::SightCache::new(),
+        fov: sight::FovSettings::new(),
+        light,
+        map_changes: vec![],
     };
+    tcod.fov = FovMap::new(game.map.width(), game.map.height());
     map::init_fov_map(&mut tcod.fov, &game);
-    let mut previous_player_position = (-1, -1);
+    tcod.activity = None;
+    lighting::update_light(&mut game, &world);
 
     // a warm welcoming message!
     game.messages.add(
-        "Welcome stranger! Prepare to perish in the Tombs of the Ancient Kings.",
+        format!(
+            "Welcome {} the {}! Prepare to perish in the Tombs of the Ancient Kings.",
+            name,
+            class.name()
+        ),
         colors::RED,
     );
+    (game, world)
+}
+
+/// run the game until the player dies or quits to the main menu
+fn play_game(tcod: &mut Tcod, game: &mut map::Game, world: &mut World) {
+    let mut previous_player_position = (-1, -1);
+    let mut previous_fov = (game.fov, 0);
+
     while !tcod.root.window_closed() {
         tcod.con.clear();
-        let player = &objects[global::PLAYER];
-        let fov_recompute = previous_player_position != player.pos();
+        let player = &world.objects[world.player];
+        // blindness and the debug key change how the FOV is computed, too
+        let fov = (game.fov, sight::player_sight_radius(&game.fov, world));
+        let fov_recompute = previous_player_position != player.pos() || previous_fov != fov;
         if fov_recompute {
+            previous_fov = fov;
             tcod.fov.compute_fov(
                 player.x,
                 player.y,
-                global::TORCH_RADIUS,
-                global::FOV_LIGHT_WALLS,
-                global::FOV_ALGO,
+                fov.1,
+                game.fov.light_walls,
+                game.fov.algorithm,
             );
+            tcod.camera
+                .center_on(player.x, player.y, game.map.width(), game.map.height());
+        }
+        match input::check_for_event(input::MOUSE | input::KEY_PRESS) {
+            Some((_, Event::Mouse(m))) => tcod.mouse = m,
+            Some((_, Event::Key(k))) => tcod.key = k,
+            _ => tcod.key = Default::default(),
         }
-        render_all(&mut tcod, &mut game, &objects);
+
+        render_all(tcod, game, world);
         tcod.root.flush();
 
-        let player = &mut objects[global::PLAYER];
         // handle keys and exit game if needed
-        previous_player_position = player.pos();
+        previous_player_position = world.pos(world.player);
 
-        let player_action = handle_keys(&mut tcod, &mut game, &mut objects);
+        // carry on with whatever the player set out to do, until they press
+        // a key or something comes up
+        let player_action = match tcod.activity.take() {
+            Some(_) if tcod.key.code != input::KeyCode::NoKey => DidntTakeTurn,
+            Some(mut activity) => {
+                if world.is_alive(world.player)
+                    && !activity.interrupted(&tcod.fov, game, world)
+                    && activity.step(game, world)
+                {
+                    tcod.activity = Some(activity);
+                    TookTurn
+                } else {
+                    DidntTakeTurn
+                }
+            }
+            None => handle_keys(tcod, game, world),
+        };
         if player_action == PlayerAction::Exit {
             break;
         }
-        // let monsters take their turn
-        if objects[global::PLAYER].alive && player_action != PlayerAction::DidntTakeTurn {
-            for id in 0..objects.len() {
-                if objects[id].ai.is_some() {
-                    ai_take_turn(id, &tcod, &mut game, &mut objects);
+        // let monsters take their turn, and a few more if the player's move
+        // was slowed down by the terrain
+        if world.is_alive(world.player) && player_action != PlayerAction::DidntTakeTurn {
+            play_turn(game, world);
+            while world.is_alive(world.player) && status::spend_delay(world.player, world) {
+                play_turn(game, world);
+            }
+        }
+        if world.is_alive(world.player) {
+            character::check_level_up(&mut tcod.root, game, world);
+        }
+        // that's the end of the game: tell the player how it went
+        if !world.is_alive(world.player) {
+            end_game(tcod, game, world);
+            break;
+        }
+        // doors opened this turn change what can be seen
+        let map_changes = game.take_map_changes();
+        for &(x, y) in &map_changes {
+            map::update_fov_map(&mut tcod.fov, game, x, y);
+        }
+        if !map_changes.is_empty() {
+            previous_player_position = (-1, -1);
+        }
+    }
+}
+
+/// Write the morgue file, show how the game ended and put it in the
+/// high-score table.
+fn end_game(tcod: &mut Tcod, game: &map::Game, world: &World) {
+    let morgue = death::write_morgue(game, world);
+    death::show_game_over(&mut tcod.root, game, world, &morgue);
+
+    let score = highscores::Score::of(game, world);
+    let (scores, place, note) = match highscores::load() {
+        Ok(mut scores) => {
+            let place = highscores::add(&mut scores, score);
+            let note = match highscores::save(&scores) {
+                Ok(()) => "Press any key to continue.".to_string(),
+                Err(error) => format!("Couldn't save the high scores: {}", error),
+            };
+            (scores, place, note)
+        }
+        // leave the file alone rather than save over scores we couldn't read
+        Err(error) => (
+            vec![score],
+            Some(0),
+            format!("Couldn't read the high scores: {}", error),
+        ),
+    };
+    highscores::show_high_scores(&mut tcod.root, &scores, place, &note);
+}
+
+fn main_menu(tcod: &mut Tcod) {
+    while !tcod.root.window_closed() {
+        tcod.root.set_default_background(colors::BLACK);
+        tcod.root.clear();
+
+        // show the game's title
+        let center_x = tcod.layout.width / 2;
+        let title_y = tcod.layout.height / 4;
+        tcod.root.set_default_foreground(colors::LIGHT_YELLOW);
+        tcod.root.print_ex(
+            center_x,
+            title_y,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            "TOMBS OF THE ANCIENT KINGS",
+        );
+        tcod.root.print_ex(
+            center_x,
+            title_y + 2,
+            BackgroundFlag::None,
+            TextAlignment::Center,
+            "By Yours Truly",
+        );
+
+        // show options and wait for the player's choice
+        let choices = &["Play a new game", "High scores", "Quit"];
+        let choice = menu::menu("", choices, global::MAIN_MENU_WIDTH, &mut tcod.root);
+        match choice {
+            Some(0) => {
+                // the player can back out of making a character, too
+                if let Some((name, class)) = creation::create_character(&mut tcod.root) {
+                    let (mut game, mut world) = new_game(tcod, &name, class);
+                    play_game(tcod, &mut game, &mut world);
                 }
             }
+            Some(1) => {
+                let (scores, note) = match highscores::load() {
+                    Ok(scores) => (scores, "Press any key to go back.".to_string()),
+                    Err(error) => (vec![], format!("Couldn't read the high scores: {}", error)),
+                };
+                highscores::show_high_scores(&mut tcod.root, &scores, None, &note);
+            }
+            Some(2) => break,
+            _ => {}
         }
     }
 }
+
+fn main() {
+    // the window can be made bigger (or smaller) with `--size`
+    let (width, height) =
+        utils::size_from_args().unwrap_or((global::SCREEN_WIDTH, global::SCREEN_HEIGHT));
+    let layout = screen::Layout::new(width, height);
+    let root = Root::initializer()
+        .font("arial10x10.png", FontLayout::Tcod)
+        .font_type(FontType::Greyscale)
+        .size(layout.width, layout.height)
+        .title("Rust/libtcod tutorial")
+        .init();
+
+    let mut tcod = Tcod {
+        root,
+        con: Offscreen::new(layout.view_width, layout.view_height),
+        panel: Offscreen::new(layout.width, global::PANEL_HEIGHT),
+        // made to fit each new level as it's generated
+        fov: FovMap::new(1, 1),
+        key: Default::default(),
+        mouse: Default::default(),
+        layout,
+        camera: screen::Camera::new(layout.view_width, layout.view_height),
+        activity: None,
+    };
+    tcod::system::set_fps(global::LIMIT_FPS);
+    main_menu(&mut tcod);
+}
@@ -1,12 +1,24 @@
+use crate::ai::{Ai, Awareness};
+use crate::combat;
+use crate::death::Cause;
 use crate::global;
-use crate::objects;
-use crate::objects::Object;
-use rand::Rng;
+use crate::grid::Grid;
+use crate::hazards::{self, Trap, TrapKind};
+use crate::items::{self, Item};
+use crate::lighting::Light;
+use crate::magic;
+use crate::objects::{DeathCallback, Fighter, Object};
+use crate::regeneration::Regeneration;
+use crate::sight::{FovSettings, SightCache, Vision};
+use crate::stats::Stats;
+use crate::terrain::Terrain;
+use crate::world::{Entity, World};
+use rand::{Rng, StdRng};
 use std::cmp;
 use tcod::colors::Color;
 use tcod::map::Map as FovMap;
 
-pub type Map = Vec<Vec<Tile>>;
+pub type Map = Grid<Tile>;
 pub struct Messages {
     messages: Vec<(String, Color)>,
 }
@@ -26,9 +38,94 @@ impl Messages {
     }
 }
 
+/// A sound loud enough to wake monsters up to `radius` tiles away.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    pub x: i32,
+    pub y: i32,
+    pub radius: i32,
+}
+
 pub struct Game {
     pub map: Map,
     pub messages: Messages,
+    pub rng: StdRng,
+    /// what the RNG was seeded with, so the run can be replayed
+    pub seed: usize,
+    /// how far down the dungeon the player is, starting from 1
+    pub depth: i32,
+    /// turns played so far
+    pub turn: i32,
+    /// everything counted over the run so far
+    pub stats: Stats,
+    /// what killed the player, once something has
+    pub death: Option<Cause>,
+    pub noises: Vec<Noise>,
+    pub sight: SightCache,
+    /// how the player's field of view is computed
+    pub fov: FovSettings,
+    /// how brightly lit each tile is, from 0 to 1
+    pub light: Grid<f32>,
+    /// tiles whose terrain changed since the last call to `take_map_changes`
+    pub map_changes: Vec<(i32, i32)>,
+}
+
+impl Game {
+    pub fn make_noise(&mut self, x: i32, y: i32, radius: i32) {
+        self.noises.push(Noise { x, y, radius });
+    }
+
+    /// all the noises made since the last call
+    pub fn take_noises(&mut self) -> Vec<Noise> {
+        std::mem::take(&mut self.noises)
+    }
+
+    /// Change what a tile is made of. Anything remembered about who can see
+    /// through it is forgotten, and the change is recorded so the FOV map can
+    /// catch up.
+    pub fn set_terrain(&mut self, x: i32, y: i32, terrain: Terrain) {
+        self.map[(x, y)].terrain = terrain;
+        self.sight.clear();
+        self.map_changes.push((x, y));
+    }
+
+    /// all the tiles changed since the last call
+    pub fn take_map_changes(&mut self) -> Vec<(i32, i32)> {
+        std::mem::take(&mut self.map_changes)
+    }
+}
+
+#[cfg(test)]
+impl Game {
+    /// a game on an open, walled-in floor of the given size, with a fixed
+    /// seed, for trying things out on a map built by hand
+    pub fn for_tests(width: i32, height: i32) -> Self {
+        use rand::SeedableRng;
+        let mut map = Grid::new(width, height, Tile::new(Terrain::Floor));
+        for x in 0..width {
+            map[(x, 0)] = Tile::new(Terrain::Wall);
+            map[(x, height - 1)] = Tile::new(Terrain::Wall);
+        }
+        for y in 0..height {
+            map[(0, y)] = Tile::new(Terrain::Wall);
+            map[(width - 1, y)] = Tile::new(Terrain::Wall);
+        }
+        Game {
+            map,
+            messages: Messages::new(),
+            rng: SeedableRng::from_seed(&[1][..]),
+            seed: 1,
+            depth: 1,
+            turn: 0,
+            stats: Stats::default(),
+            death: None,
+            noises: vec![],
+            sight: SightCache::new(),
+            fov: FovSettings::new(),
+            light: Grid::new(width, height, 1.0),
+            map_changes: vec![],
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -70,29 +167,107 @@ fn create_room(room: Rect, map: &mut Map) {
     // go through the tiles in the rectangle and make them passable
     for x in (room.x1 + 1)..room.x2 {
         for y in (room.y1 + 1)..room.y2 {
-            map[x as usize][y as usize] = Tile::empty();
+            map[(x, y)] = Tile::new(Terrain::Floor);
         }
     }
 }
 fn create_h_tunnel(x1: i32, x2: i32, y: i32, map: &mut Map) {
     // horizontal tunnel. `min()` and `max()` are used in case `x1 > x2`
     for x in cmp::min(x1, x2)..(cmp::max(x1, x2) + 1) {
-        map[x as usize][y as usize] = Tile::empty();
+        map[(x, y)] = Tile::new(Terrain::Floor);
     }
 }
 fn create_v_tunnel(y1: i32, y2: i32, x: i32, map: &mut Map) {
     // vertical tunnel
     for y in cmp::min(y1, y2)..(cmp::max(y1, y2) + 1) {
-        map[x as usize][y as usize] = Tile::empty();
+        map[(x, y)] = Tile::new(Terrain::Floor);
+    }
+}
+
+/// Put doors into some of the gaps the tunnels made in the room's walls,
+/// wherever there's a wall on either side to hang them in.
+fn create_doors(room: Rect, map: &mut Map, rng: &mut StdRng) {
+    let is_wall = |map: &Map, x: i32, y: i32| {
+        map.get(x, y)
+            .map_or(true, |tile: &Tile| tile.terrain == Terrain::Wall)
+    };
+    let mut walls = vec![];
+    for x in (room.x1 + 1)..room.x2 {
+        walls.push((x, room.y1));
+        walls.push((x, room.y2));
+    }
+    for y in (room.y1 + 1)..room.y2 {
+        walls.push((room.x1, y));
+        walls.push((room.x2, y));
+    }
+    for (x, y) in walls {
+        if map[(x, y)].terrain != Terrain::Floor {
+            continue;
+        }
+        let doorway = (is_wall(map, x - 1, y) && is_wall(map, x + 1, y))
+            || (is_wall(map, x, y - 1) && is_wall(map, x, y + 1));
+        if doorway && rng.gen::<f32>() < global::DOOR_CHANCE {
+            map[(x, y)] = Tile::new(Terrain::Door);
+        }
+    }
+}
+
+/// Maybe cover part of the room in something other than bare floor.
+fn create_features(room: Rect, map: &mut Map, rng: &mut StdRng) {
+    let roll = rng.gen::<f32>();
+    if roll < global::POOL_CHANCE {
+        create_pool(room, map, rng);
+    } else if roll < global::POOL_CHANCE + global::LAVA_CHANCE {
+        create_patch(room, Terrain::Lava, map, rng);
+    } else if roll < global::POOL_CHANCE + global::LAVA_CHANCE + global::RUBBLE_CHANCE {
+        create_patch(room, Terrain::Rubble, map, rng);
+    }
+}
+
+/// deep water, with a shallow edge wherever there's floor around it
+fn create_pool(room: Rect, map: &mut Map, rng: &mut StdRng) {
+    let deep = create_patch(room, Terrain::DeepWater, map, rng);
+    for (x, y) in deep {
+        for (x, y) in map.neighbours(x, y).collect::<Vec<_>>() {
+            if room.contains(x, y) && map[(x, y)].terrain == Terrain::Floor {
+                map[(x, y)] = Tile::new(Terrain::ShallowWater);
+            }
+        }
+    }
+}
+
+/// a blob of terrain grown one neighbouring tile at a time from a random
+/// spot in the room
+fn create_patch(room: Rect, terrain: Terrain, map: &mut Map, rng: &mut StdRng) -> Vec<(i32, i32)> {
+    let size = rng.gen_range(2, global::MAX_PATCH_SIZE + 1) as usize;
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = rng.gen_range(room.y1 + 1, room.y2);
+    let mut patch = vec![(x, y)];
+    for _ in 0..size * 4 {
+        if patch.len() >= size {
+            break;
+        }
+        let (x, y) = patch[rng.gen_range(0, patch.len())];
+        let free = map
+            .neighbours(x, y)
+            .filter(|&(x, y)| room.contains(x, y) && !patch.contains(&(x, y)))
+            .collect::<Vec<_>>();
+        if !free.is_empty() {
+            patch.push(free[rng.gen_range(0, free.len())]);
+        }
+    }
+    for &(x, y) in &patch {
+        map[(x, y)] = Tile::new(terrain);
     }
+    patch
 }
 
-fn create_random_room(width: i32, height: i32) -> Rect {
-    let w = rand::thread_rng().gen_range(global::ROOM_MIN_SIZE, global::ROOM_MAX_SIZE + 1);
-    let h = rand::thread_rng().gen_range(global::ROOM_MIN_SIZE, global::ROOM_MAX_SIZE + 1);
+fn create_random_room(width: i32, height: i32, rng: &mut StdRng) -> Rect {
+    let w = rng.gen_range(global::ROOM_MIN_SIZE, global::ROOM_MAX_SIZE + 1);
+    let h = rng.gen_range(global::ROOM_MIN_SIZE, global::ROOM_MAX_SIZE + 1);
     // random position without going out of the boundaries of the map
-    let x = rand::thread_rng().gen_range(0, width - w);
-    let y = rand::thread_rng().gen_range(0, height - h);
+    let x = rng.gen_range(0, width - w);
+    let y = rng.gen_range(0, height - h);
 
     Rect::new(x, y, w, h)
 }
@@ -101,23 +276,24 @@ pub fn generate(
     height: i32,
     start_x: i32,
     start_y: i32,
-    objs: &mut Vec<Object>,
+    world: &mut World,
+    rng: &mut StdRng,
 ) -> Map {
     // fill map with wall tiles
-    let mut map = vec![vec![Tile::wall(); height as usize]; width as usize];
+    let mut map = Grid::new(width, height, Tile::new(Terrain::Wall));
 
     let mut rooms = vec![];
 
     let mut first_room = Rect::new(0, 0, 0, 0);
     while !first_room.contains(start_x, start_y) {
-        first_room = create_random_room(width, height);
+        first_room = create_random_room(width, height, rng);
     }
     create_room(first_room, &mut map);
     rooms.push(first_room);
 
     for _ in 0..global::MAX_ROOMS {
         // random width and height
-        let new_room = create_random_room(width, height);
+        let new_room = create_random_room(width, height, rng);
 
         // run through the other rooms and see if they intersect with this one
         let failed = rooms
@@ -133,7 +309,7 @@ pub fn generate(
                 let (prev_x, prev_y) = rooms[rooms.len() - 1].center();
 
                 // toss a coin (random bool value -- either true or false)
-                if rand::random() {
+                if rng.gen() {
                     // first move horizontally, then vertically
                     create_h_tunnel(prev_x, new_x, prev_y, &mut map);
                     create_v_tunnel(prev_y, new_y, new_x, &mut map);
@@ -144,102 +320,440 @@ pub fn generate(
                 }
             }
             rooms.push(new_room);
-            place_objects(new_room, &map, objs);
+            create_features(new_room, &mut map, rng);
+            place_objects(new_room, &map, world, rng);
+            place_traps(new_room, &map, world, rng);
+            place_items(new_room, &map, world, rng);
         }
     }
+    // only now that every tunnel is dug can we tell where the doorways are
+    for &room in &rooms {
+        create_doors(room, &mut map, rng);
+        place_brazier(room, &map, world, rng);
+    }
 
     map
 }
 
 pub fn init_fov_map(fov: &mut FovMap, game: &Game) {
-    for y in 0..global::MAP_HEIGHT {
-        for x in 0..global::MAP_WIDTH {
-            fov.set(
-                x,
-                y,
-                !game.map[x as usize][y as usize].block_sight,
-                !game.map[x as usize][y as usize].blocked,
-            );
-        }
+    for ((x, y), _) in game.map.iter() {
+        update_fov_map(fov, game, x, y);
     }
 }
 
-/// A tile of the map and its properties
+/// bring a single tile of the FOV map up to date with the game map
+pub fn update_fov_map(fov: &mut FovMap, game: &Game, x: i32, y: i32) {
+    let tile = &game.map[(x, y)];
+    fov.set(x, y, !tile.block_sight(), !tile.blocked());
+}
+
+/// A tile of the map: what it's made of, and whether the player has seen it
 #[derive(Clone, Copy, Debug)]
 pub struct Tile {
-    pub blocked: bool,
-    pub block_sight: bool,
+    pub terrain: Terrain,
     pub explored: bool,
-    pub color: Color,
+    /// what it looked like the last time the player saw it
+    pub memory: Memory,
+}
+
+/// What the player remembers of a tile once it's out of sight. It only gets
+/// updated when they see the tile again, so a door closed behind their back
+/// or a corpse that rots away meanwhile is remembered as it was.
+#[derive(Clone, Copy, Debug)]
+pub struct Memory {
+    pub terrain: Terrain,
+    /// glyph and color of whatever lay there that stays put
+    pub object: Option<(char, Color)>,
 }
 
 impl Tile {
-    pub fn empty() -> Self {
+    pub fn new(terrain: Terrain) -> Self {
         Tile {
-            blocked: false,
-            block_sight: false,
+            terrain,
             explored: false,
-            color: global::COLOR_DARK_GROUND,
+            memory: Memory {
+                terrain,
+                object: None,
+            },
         }
     }
 
-    pub fn wall() -> Self {
-        Tile {
-            blocked: true,
-            block_sight: true,
-            explored: false,
-            color: global::COLOR_DARK_WALL,
+    pub fn blocked(&self) -> bool {
+        self.terrain.info().blocks
+    }
+
+    pub fn block_sight(&self) -> bool {
+        self.terrain.info().blocks_sight
+    }
+}
+
+/// Note down what the player sees on a tile: its terrain, and the topmost
+/// thing lying there that doesn't move around by itself, like a corpse, a
+/// trap they've found or a brazier. Monsters aren't worth remembering.
+/// Returns `true` if the player had never seen the tile before.
+pub fn remember_tile(x: i32, y: i32, map: &mut Map, world: &World) -> bool {
+    let object = world
+        .at(x, y)
+        .iter()
+        .rev()
+        .copied()
+        .find(|&entity| !world.fighters.contains(entity) && !hazards::is_hidden(entity, world))
+        .map(|entity| (world.objects[entity].character, world.objects[entity].color));
+    let tile = &mut map[(x, y)];
+    let first_time = !tile.explored;
+    tile.explored = true;
+    tile.memory = Memory {
+        terrain: tile.terrain,
+        object,
+    };
+    first_time
+}
+
+/// Reveal the layout of the whole level, as if the player had walked every
+/// room and corridor. Solid rock stays dark, apart from the walls around them.
+/// Returns how many tiles weren't explored before.
+pub fn magic_mapping(map: &mut Map) -> i32 {
+    let revealed = map
+        .iter()
+        .filter(|&((x, y), tile)| {
+            tile.terrain != Terrain::Wall
+                || map
+                    .neighbours(x, y)
+                    .any(|(nx, ny)| map[(nx, ny)].terrain != Terrain::Wall)
+        })
+        .map(|(pos, _)| pos)
+        .collect::<Vec<_>>();
+    let mut first_time = 0;
+    for pos in revealed {
+        let tile = &mut map[pos];
+        if !tile.explored {
+            first_time += 1;
         }
+        tile.explored = true;
+        tile.memory.terrain = tile.terrain;
     }
+    first_time
 }
-fn place_objects(room: Rect, map: &Map, objects: &mut Vec<Object>) {
+
+fn place_objects(room: Rect, map: &Map, world: &mut World, rng: &mut StdRng) {
     // choose random number of monsters
-    let num_monsters = rand::thread_rng().gen_range(0, global::MAX_ROOM_MONSTERS + 1);
+    let num_monsters = rng.gen_range(0, global::MAX_ROOM_MONSTERS + 1);
 
     for _ in 0..num_monsters {
         // choose random spot for this monster
-        let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
-        let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
-        if !is_blocked(x, y, map, objects) {
-            let mut monster = if rand::random::<f32>() < 0.8 {
-                // 80% chance of getting an orc
+        let x = rng.gen_range(room.x1 + 1, room.x2);
+        let y = rng.gen_range(room.y1 + 1, room.y2);
+        // monsters only start out on bare floor
+        if is_open_floor(x, y, map, world) {
+            let awake = rng.gen::<f32>() >= global::ASLEEP_CHANCE;
+            let roll = rng.gen::<f32>();
+            let mut glows = false;
+            let (monster, fighter, sight_radius, ai) = if roll < 0.6 {
+                // 60% chance of getting an orc
                 // create an orc
-                let mut orc = Object::new(x, y, 'o', "orc", global::ORC_COLOR, true);
-                orc.fighter = Some(objects::Fighter {
+                let orc = Object::new(x, y, 'o', "orc", global::ORC_COLOR, true);
+                let fighter = Fighter {
                     max_hp: 10,
                     hp: 10,
                     defense: 0,
-                    power: 3,
-                    on_death: objects::DeathCallback::Monster,
-                });
-                orc.ai = Some(objects::Ai::Basic);
-                orc
-            } else {
-                let mut troll = Object::new(x, y, 'T', "troll", global::TROLL_COLOR, true);
-                troll.fighter = Some(objects::Fighter {
+                    power: 1,
+                    accuracy: 0,
+                    evasion: 5,
+                    damage: combat::Dice::new(1, 3),
+                    xp: 35,
+                    level: 1,
+                    on_death: DeathCallback::Monster,
+                };
+                (orc, fighter, 8, Ai::Basic)
+            } else if roll < 0.75 {
+                let troll = Object::new(x, y, 'T', "troll", global::TROLL_COLOR, true);
+                let fighter = Fighter {
                     max_hp: 16,
                     hp: 16,
                     defense: 1,
-                    power: 4,
-                    on_death: objects::DeathCallback::Monster,
-                });
-                troll.ai = Some(objects::Ai::Basic);
-                troll
+                    power: 2,
+                    accuracy: -5,
+                    evasion: 0,
+                    damage: combat::Dice::new(1, 6),
+                    xp: 100,
+                    level: 1,
+                    on_death: DeathCallback::Monster,
+                };
+                (troll, fighter, 6, Ai::Basic)
+            } else if roll < 0.85 {
+                let archer = Object::new(
+                    x,
+                    y,
+                    'g',
+                    "goblin archer",
+                    global::GOBLIN_ARCHER_COLOR,
+                    true,
+                );
+                let fighter = Fighter {
+                    max_hp: 6,
+                    hp: 6,
+                    defense: 0,
+                    power: 0,
+                    accuracy: 5,
+                    evasion: 10,
+                    damage: combat::Dice::new(1, 4),
+                    xp: 40,
+                    level: 1,
+                    on_death: DeathCallback::Monster,
+                };
+                let ai = Ai::Ranged {
+                    range: global::ARCHER_RANGE,
+                };
+                (archer, fighter, 10, ai)
+            } else if roll < 0.9 {
+                // jackals never come alone: the rest of the pack joins this one
+                for _ in 1..global::JACKAL_PACK_SIZE {
+                    let x = rng.gen_range(room.x1 + 1, room.x2);
+                    let y = rng.gen_range(room.y1 + 1, room.y2);
+                    if is_open_floor(x, y, map, world) {
+                        spawn_jackal(x, y, awake, world);
+                    }
+                }
+                // one of the pack may have taken this one's spot
+                if is_open_floor(x, y, map, world) {
+                    spawn_jackal(x, y, awake, world);
+                }
+                continue;
+            } else if roll < 0.96 {
+                let necromancer =
+                    Object::new(x, y, 'N', "necromancer", global::NECROMANCER_COLOR, true);
+                let fighter = Fighter {
+                    max_hp: 8,
+                    hp: 8,
+                    defense: 0,
+                    power: 0,
+                    accuracy: 0,
+                    evasion: 5,
+                    damage: combat::Dice::new(1, 4),
+                    xp: 120,
+                    level: 1,
+                    on_death: DeathCallback::Monster,
+                };
+                // its eyes glow with an eerie light
+                glows = true;
+                (necromancer, fighter, 8, Ai::Necromancer)
+            } else {
+                let rat = Object::new(x, y, 'r', "giant rat", global::RAT_COLOR, true);
+                let fighter = Fighter {
+                    max_hp: 4,
+                    hp: 4,
+                    defense: 0,
+                    power: 0,
+                    accuracy: 0,
+                    evasion: 15,
+                    damage: combat::Dice::new(1, 3),
+                    xp: 10,
+                    level: 1,
+                    on_death: DeathCallback::Monster,
+                };
+                (rat, fighter, 4, Ai::Wandering)
             };
 
-            monster.alive = true;
-            objects.push(monster);
+            let monster = spawn_monster(monster, fighter, sight_radius, ai, awake, world);
+            if glows {
+                world.lights.insert(
+                    monster,
+                    Light {
+                        radius: global::GLOW_RADIUS,
+                        intensity: global::GLOW_INTENSITY,
+                        fuel: None,
+                    },
+                );
+            }
         }
     }
 }
 
-pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
+/// maybe hang a brazier on one of the room's walls, to light it up
+fn place_brazier(room: Rect, map: &Map, world: &mut World, rng: &mut StdRng) {
+    if rng.gen::<f32>() >= global::BRAZIER_CHANCE {
+        return;
+    }
+    // pick a spot on the top or bottom wall, avoiding doorways
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = if rng.gen() { room.y1 } else { room.y2 };
+    if map[(x, y)].terrain != Terrain::Wall {
+        return;
+    }
+    let brazier = world.spawn(Object::new(
+        x,
+        y,
+        '*',
+        "brazier",
+        global::BRAZIER_COLOR,
+        false,
+    ));
+    world.lights.insert(
+        brazier,
+        Light {
+            radius: global::BRAZIER_RADIUS,
+            intensity: global::BRAZIER_INTENSITY,
+            fuel: None,
+        },
+    );
+}
+
+fn place_traps(room: Rect, map: &Map, world: &mut World, rng: &mut StdRng) {
+    if rng.gen::<f32>() >= global::TRAP_CHANCE {
+        return;
+    }
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = rng.gen_range(room.y1 + 1, room.y2);
+    if !is_open_floor(x, y, map, world) {
+        return;
+    }
+    let (name, kind) = match rng.gen_range(0, 3) {
+        0 => ("spike trap", TrapKind::Spikes),
+        1 => ("alarm trap", TrapKind::Alarm),
+        _ => ("flash trap", TrapKind::Flash),
+    };
+    let trap = world.spawn(Object::new(x, y, '^', name, global::TRAP_COLOR, false));
+    world.traps.insert(trap, Trap { kind, hidden: true });
+}
+
+fn place_items(room: Rect, map: &Map, world: &mut World, rng: &mut StdRng) {
+    let mut roll = rng.gen::<f32>();
+    // pick whichever item's chance the roll falls within
+    let mut chance = |item_chance: f32| {
+        roll -= item_chance;
+        roll < 0.0
+    };
+    let item = if chance(global::RATION_CHANCE) {
+        Item::Ration
+    } else if chance(global::POTION_CHANCE) {
+        Item::HealingPotion
+    } else if chance(global::SWORD_CHANCE) {
+        Item::Sword
+    } else if chance(global::SHIELD_CHANCE) {
+        Item::Shield
+    } else if chance(global::LIGHTNING_SCROLL_CHANCE) {
+        Item::LightningScroll
+    } else if chance(global::MAPPING_SCROLL_CHANCE) {
+        Item::MagicMappingScroll
+    } else if chance(global::LIGHT_SCROLL_CHANCE) {
+        Item::LightScroll
+    } else if chance(global::BOOK_CHANCE) {
+        Item::Book(magic::SPELLS[rng.gen_range(0, magic::SPELLS.len())])
+    } else {
+        return;
+    };
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = rng.gen_range(room.y1 + 1, room.y2);
+    if !is_open_floor(x, y, map, world) {
+        return;
+    }
+    items::spawn_item(item, x, y, world);
+}
+
+/// add a monster to the world along with everything it needs to think and fight
+fn spawn_monster(
+    monster: Object,
+    fighter: Fighter,
+    sight_radius: i32,
+    ai: Ai,
+    awake: bool,
+    world: &mut World,
+) -> Entity {
+    let entity = world.spawn(monster);
+    world.fighters.insert(entity, fighter);
+    world.ais.insert(entity, ai);
+    world.awareness.insert(
+        entity,
+        Awareness {
+            awake,
+            last_known_player_pos: None,
+        },
+    );
+    world.vision.insert(
+        entity,
+        Vision {
+            radius: sight_radius,
+        },
+    );
+    // so that a monster that ran off to lick its wounds comes back for more
+    world
+        .regeneration
+        .insert(entity, Regeneration::new(global::REGENERATION_RATE));
+    entity
+}
+
+fn spawn_jackal(x: i32, y: i32, awake: bool, world: &mut World) -> Entity {
+    let jackal = Object::new(x, y, 'j', "jackal", global::JACKAL_COLOR, true);
+    let fighter = Fighter {
+        max_hp: 5,
+        hp: 5,
+        defense: 0,
+        power: 0,
+        accuracy: 0,
+        evasion: 10,
+        damage: combat::Dice::new(1, 3),
+        xp: 15,
+        level: 1,
+        on_death: DeathCallback::Monster,
+    };
+    let ai = Ai::Pack { alerted: false };
+    spawn_monster(jackal, fighter, 8, ai, awake, world)
+}
+
+/// Like `is_blocked`, but also keeps monsters off tiles they know to be
+/// dangerous: lava, and traps (they know where those are, even hidden ones).
+pub fn is_blocked_for(entity: Entity, x: i32, y: i32, game: &Game, world: &World) -> bool {
+    is_blocked(x, y, &game.map, world) || avoids(entity, x, y, game, world)
+}
+
+fn avoids(entity: Entity, x: i32, y: i32, game: &Game, world: &World) -> bool {
+    entity != world.player
+        && (game.map[(x, y)].terrain.info().hazardous || hazards::trap_at(x, y, world).is_some())
+}
+
+/// What it costs `entity` to step onto a tile when planning a path, or `None`
+/// if it won't go there at all.
+pub fn path_cost(entity: Entity, x: i32, y: i32, game: &Game, world: &World) -> Option<i32> {
+    let terrain = game.map.get(x, y)?.terrain;
+    let info = terrain.info();
+    // closed doors are in the way, but can be opened
+    if (info.blocks && terrain != Terrain::Door) || avoids(entity, x, y, game, world) {
+        return None;
+    }
+    // whoever stands there now may well have moved on by the time we arrive
+    if world.blocking_at(x, y).is_some() {
+        Some(info.move_cost + global::CROWD_PATH_COST)
+    } else {
+        Some(info.move_cost)
+    }
+}
+
+/// bare floor with nothing in the way: somewhere a monster or item can be
+/// put down when a level is made
+fn is_open_floor(x: i32, y: i32, map: &Map, world: &World) -> bool {
+    map[(x, y)].terrain == Terrain::Floor && !is_blocked(x, y, map, world)
+}
+
+pub fn is_blocked(x: i32, y: i32, map: &Map, world: &World) -> bool {
     // first test the map tile
-    if map[x as usize][y as usize].blocked {
+    if map.get(x, y).map_or(true, |tile| tile.blocked()) {
         return true;
     }
     // now check for any blocking objects
-    objects
-        .iter()
-        .any(|object| object.blocks && object.pos() == (x, y))
+    world.blocking_at(x, y).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_mapping_counts_only_new_tiles() {
+        let mut game = Game::for_tests(10, 8);
+        game.map[(3, 3)].explored = true;
+        // the whole room is floor or a wall next to it
+        assert_eq!(magic_mapping(&mut game.map), 10 * 8 - 1);
+        assert!(game.map.iter().all(|(_, tile)| tile.explored));
+        assert_eq!(magic_mapping(&mut game.map), 0);
+    }
 }
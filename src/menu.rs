@@ -0,0 +1,103 @@
+use crate::global;
+use crate::world::{Entity, World};
+use tcod::colors;
+use tcod::console::*;
+
+/// Show a window in the middle of the screen with a list of options, each
+/// picked by pressing its letter, and wait for the player to choose. Returns
+/// the index of the option they picked, or `None` for any other key.
+pub fn menu<T: AsRef<str>>(
+    header: &str,
+    options: &[T],
+    width: i32,
+    root: &mut Root,
+) -> Option<usize> {
+    assert!(
+        options.len() <= 26,
+        "Cannot have a menu with more than 26 options."
+    );
+
+    // calculate total height for the header (after auto-wrap) and one line per option
+    let header_height = if header.is_empty() {
+        0
+    } else {
+        root.get_height_rect(0, 0, width, root.height(), header)
+    };
+    let height = options.len() as i32 + header_height;
+
+    // create an off-screen console that represents the menu's window
+    let mut window = Offscreen::new(width, height);
+
+    // print the header, with auto-wrap
+    window.set_default_foreground(colors::WHITE);
+    window.print_rect_ex(
+        0,
+        0,
+        width,
+        height,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        header,
+    );
+
+    // print all the options
+    for (index, option_text) in options.iter().enumerate() {
+        let menu_letter = (b'a' + index as u8) as char;
+        let text = format!("({}) {}", menu_letter, option_text.as_ref());
+        window.print_ex(
+            0,
+            header_height + index as i32,
+            BackgroundFlag::None,
+            TextAlignment::Left,
+            text,
+        );
+    }
+
+    // blit the contents of "window" to the root console
+    let x = root.width() / 2 - width / 2;
+    let y = root.height() / 2 - height / 2;
+    blit(&window, (0, 0), (width, height), root, (x, y), 1.0, 0.7);
+
+    // present the root console to the player and wait for a key-press
+    root.flush();
+    let key = root.wait_for_keypress(true);
+
+    // convert the ASCII code to an index; if it corresponds to an option, return it
+    if key.printable.is_alphabetic() {
+        let index = key.printable.to_ascii_lowercase() as usize - 'a' as usize;
+        if index < options.len() {
+            Some(index)
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+/// Show a menu with each item the player is carrying as an option, and
+/// return the one they picked.
+pub fn inventory_menu(world: &World, header: &str, root: &mut Root) -> Option<Entity> {
+    let options = if world.inventory.is_empty() {
+        vec!["Inventory is empty.".to_string()]
+    } else {
+        world
+            .inventory
+            .iter()
+            .map(|&item| {
+                // show which items are equipped, and where
+                match world.equipment.get(item) {
+                    Some(equipment) if equipment.equipped => {
+                        format!("{} (on {})", world.name(item), equipment.slot)
+                    }
+                    _ => world.name(item).to_string(),
+                }
+            })
+            .collect()
+    };
+
+    let index = menu(header, &options, global::INVENTORY_WIDTH, root);
+
+    // if an item was chosen, return it
+    index.and_then(|index| world.inventory.get(index).copied())
+}
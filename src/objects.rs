@@ -1,37 +1,43 @@
+use crate::ai::Ai;
+use crate::combat;
+use crate::corpses::Corpse;
+use crate::death::Cause;
+use crate::equipment;
 use crate::global;
+use crate::hazards;
+use crate::hunger;
 use crate::map;
-use crate::utils;
+use crate::pathfinding;
+use crate::screen::Camera;
+use crate::status::Effect;
+use crate::terrain::Terrain;
+use crate::world::{Entity, World};
 use tcod::colors::*;
 use tcod::BackgroundFlag;
 use tcod::Console;
 
 /// This is a generic object: the player, a monster, an item, the stairs...
-/// It's always represented by a character on screen.
+/// It's always represented by a character on screen. Everything else an
+/// entity can have lives in the component storages of `World`.
 #[derive(Debug)]
 pub struct Object {
     pub x: i32,
     pub y: i32,
-    character: char,
-    color: Color,
+    pub character: char,
+    pub color: Color,
     pub name: String,
     pub blocks: bool,
-    pub alive: bool,
-    pub fighter: Option<Fighter>,
-    pub ai: Option<Ai>,
 }
 
 impl Object {
     pub fn new(x: i32, y: i32, character: char, name: &str, color: Color, blocks: bool) -> Self {
         Object {
-            x: x,
-            y: y,
-            character: character,
+            x,
+            y,
+            character,
             name: name.into(),
-            color: color,
-            blocks: blocks,
-            alive: false,
-            fighter: None,
-            ai: None,
+            color,
+            blocks,
         }
     }
 
@@ -41,93 +47,236 @@ impl Object {
         ((dx.pow(2) + dy.pow(2)) as f32).sqrt()
     }
 
+    /// return the distance to some coordinates
+    pub fn distance_to_pos(&self, x: i32, y: i32) -> f32 {
+        (((x - self.x).pow(2) + (y - self.y).pow(2)) as f32).sqrt()
+    }
+
     pub fn pos(&self) -> (i32, i32) {
         (self.x, self.y)
     }
 
-    pub fn set_pos(&mut self, x: i32, y: i32) {
-        self.x = x;
-        self.y = y;
+    /// set the color and then draw the character that represents this object
+    /// at its position, if the camera can see it
+    pub fn draw(&self, con: &mut dyn Console, camera: &Camera) {
+        if let Some((x, y)) = camera.screen_pos(self.x, self.y) {
+            con.set_default_foreground(self.color);
+            con.put_char(x, y, self.character, BackgroundFlag::None);
+        }
+    }
+}
+
+/// the name as shown when looking at an entity, e.g. "orc (asleep)"
+pub fn description(entity: Entity, world: &World) -> String {
+    let name = world.name(entity);
+    if let Some(awareness) = world.awareness.get(entity) {
+        if awareness.awake {
+            format!("{} (awake)", name)
+        } else {
+            format!("{} (asleep)", name)
+        }
+    } else if world.corpses.get(entity).map_or(false, |c| c.is_rotten()) {
+        format!("{} (rotting)", name)
+    } else {
+        name.to_string()
     }
+}
 
-    /// set the color and then draw the character that represents this object at its position
-    pub fn draw(&self, con: &mut dyn Console) {
-        con.set_default_foreground(self.color);
-        con.put_char(self.x, self.y, self.character, BackgroundFlag::None);
+/// heal by the given amount, without going over the maximum
+pub fn heal(entity: Entity, amount: i32, world: &mut World) {
+    let max_hp = equipment::max_hp(entity, world);
+    if let Some(fighter) = world.fighters.get_mut(entity) {
+        fighter.hp = (fighter.hp + amount).min(max_hp);
     }
-    pub fn take_damage(&mut self, damage: i32, game: &mut map::Game) {
-        // apply damage if possible
-        if let Some(fighter) = self.fighter.as_mut() {
-            if damage > 0 {
-                fighter.hp -= damage;
+}
+
+pub fn take_damage(
+    entity: Entity,
+    damage: i32,
+    cause: Cause,
+    game: &mut map::Game,
+    world: &mut World,
+) {
+    // apply damage if possible
+    if let Some(fighter) = world.fighters.get_mut(entity) {
+        if damage > 0 {
+            fighter.hp -= damage;
+            if entity == world.player {
+                game.stats.damage_taken += damage;
             }
         }
-        if let Some(fighter) = self.fighter {
-            if fighter.hp <= 0 {
-                self.alive = false;
-                fighter.on_death.callback(self, game);
+    }
+    if let Some(fighter) = world.fighters.get(entity).copied() {
+        if fighter.hp <= 0 {
+            if entity == world.player {
+                // the player only dies once: anything that hits the corpse
+                // before the game notices changes nothing
+                if game.death.is_some() {
+                    return;
+                }
+                // remember what did them in, for the morgue
+                game.death = Some(cause);
             }
+            fighter.on_death.callback(entity, game, world);
         }
     }
-    pub fn attack(&mut self, target: &mut Object, game: &mut map::Game) {
-        // a simple formula for attack damage
-        let damage = self.fighter.map_or(0, |f| f.power) - target.fighter.map_or(0, |f| f.defense);
-        if damage > 0 {
-            // make the target take some damage
+}
+
+/// a ranged attack: resolved like a melee one, just from further away
+pub fn shoot(attacker: Entity, target: Entity, game: &mut map::Game, world: &mut World) {
+    game.messages.add(
+        format!("{} shoots at {}.", world.name(attacker), world.name(target)),
+        WHITE,
+    );
+    attack(attacker, target, game, world);
+}
+
+pub fn attack(attacker: Entity, target: Entity, game: &mut map::Game, world: &mut World) {
+    let (attacker_stats, defender_stats) =
+        match (world.fighters.get(attacker), world.fighters.get(target)) {
+            (Some(&attacker), Some(&defender)) => (attacker, defender),
+            _ => return,
+        };
+    let attacker_stats = equipment::with_bonuses(attacker, attacker_stats, world);
+    let attacker_stats = hunger::weakened(attacker, attacker_stats, world);
+    let defender_stats = equipment::with_bonuses(target, defender_stats, world);
+    // fighting is loud, hit or miss
+    let (target_x, target_y) = world.pos(target);
+    game.make_noise(target_x, target_y, global::COMBAT_NOISE_RADIUS);
+    let attacker_name = world.name(attacker).to_string();
+    let target_name = world.name(target).to_string();
+    let outcome = combat::resolve_attack(&attacker_stats, &defender_stats, &mut game.rng);
+    if attacker == world.player {
+        game.stats.damage_dealt += outcome.damage();
+    }
+    match outcome {
+        combat::AttackOutcome::Miss => {
+            game.messages.add(
+                format!("{} attacks {} but misses.", attacker_name, target_name),
+                WHITE,
+            );
+        }
+        combat::AttackOutcome::Hit(damage) => {
             game.messages.add(
                 format!(
                     "{} attacks {} for {} hit points.",
-                    self.name, target.name, damage
+                    attacker_name, target_name, damage
                 ),
                 WHITE,
             );
-            target.take_damage(damage, game);
-        } else {
+            take_damage(target, damage, Cause::Monster(attacker_name), game, world);
+        }
+        combat::AttackOutcome::Critical(damage) => {
             game.messages.add(
-            format!(
-                "{} attacks {} but it has no effect!",
-                self.name, target.name
-            ),
-            WHITE,
-        );
+                format!(
+                    "{} critically hits {} for {} hit points!",
+                    attacker_name, target_name, damage
+                ),
+                YELLOW,
+            );
+            take_damage(target, damage, Cause::Monster(attacker_name), game, world);
         }
     }
+    if attacker == world.player && defender_stats.hp > 0 && !world.is_alive(target) {
+        credit_kill(&target_name, defender_stats.xp, game, world);
+    }
+}
+
+/// the player earns experience for everything they kill, however they do it
+pub fn credit_kill(target_name: &str, xp: i32, game: &mut map::Game, world: &mut World) {
+    game.stats.record_kill(target_name);
+    if let Some(player) = world.fighters.get_mut(world.player) {
+        player.xp += xp;
+    }
 }
 
-fn player_death(player: &mut Object, game: &mut map::Game) {
+fn player_death(player: Entity, game: &mut map::Game, world: &mut World) {
     // the game ended!
     game.messages.add("You died!", RED);
 
     // for added effect, transform the player into a corpse!
+    let player = &mut world.objects[player];
     player.character = '%';
     player.color = global::DEAD_COLOR;
 }
 
-fn monster_death(monster: &mut Object, game: &mut map::Game) {
+fn monster_death(monster: Entity, game: &mut map::Game, world: &mut World) {
     // transform it into a nasty corpse! it doesn't block, can't be
-    // attacked and doesn't move
+    // attacked and doesn't move, but it will rot away eventually
+    let fighter = world.fighters.remove(monster);
+    // whoever eats a necromancer picks up some of its dark insight
+    let grants = match world.ais.remove(monster) {
+        Some(Ai::Necromancer) => Some(Effect::Telepathic),
+        Some(Ai::Fleeing { previous_ai }) if *previous_ai == Ai::Necromancer => {
+            Some(Effect::Telepathic)
+        }
+        _ => None,
+    };
+    world.awareness.remove(monster);
+    world.lights.remove(monster);
+    world.regeneration.remove(monster);
+    // nothing more can ail it or hold it up
+    world.statuses.remove(monster);
+    world.delays.remove(monster);
+    let object = &mut world.objects[monster];
+    game.messages
+        .add(format!("{} is dead!", object.name), ORANGE);
+    object.character = '%';
+    object.color = global::DEAD_COLOR;
+    object.blocks = false;
+    let corpse = Corpse {
+        of: object.name.clone(),
+        turns_left: global::CORPSE_DECAY_TURNS,
+        fighter,
+        nourishment: fighter.map_or(0, |f| f.max_hp / 2),
+        grants,
+    };
+    object.name = format!("remains of {}", object.name);
+    world.corpses.insert(monster, corpse);
+}
+
+fn undead_death(monster: Entity, game: &mut map::Game, world: &mut World) {
+    // the undead fall apart for good: nothing left to raise or eat
+    world.fighters.remove(monster);
+    world.ais.remove(monster);
+    world.awareness.remove(monster);
+    world.lights.remove(monster);
+    world.regeneration.remove(monster);
+    // nothing more can ail it or hold it up
+    world.statuses.remove(monster);
+    world.delays.remove(monster);
+    let object = &mut world.objects[monster];
     game.messages
-        .add(format!("{} is dead!", monster.name), ORANGE);
-    monster.character = '%';
-    monster.color = global::DEAD_COLOR;
-    monster.blocks = false;
-    monster.fighter = None;
-    monster.ai = None;
-    monster.name = format!("remains of {}", monster.name);
+        .add(format!("{} collapses into dust!", object.name), ORANGE);
+    object.character = '%';
+    object.color = global::DEAD_COLOR;
+    object.blocks = false;
+    let corpse = Corpse {
+        of: object.name.clone(),
+        turns_left: global::CORPSE_DECAY_TURNS,
+        fighter: None,
+        nourishment: 0,
+        grants: None,
+    };
+    object.name = format!("dust of {}", object.name);
+    world.corpses.insert(monster, corpse);
 }
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DeathCallback {
     Player,
     Monster,
+    Undead,
 }
 impl DeathCallback {
-    fn callback(self, object: &mut Object, game: &mut map::Game) {
+    fn callback(self, entity: Entity, game: &mut map::Game, world: &mut World) {
         use DeathCallback::*;
-        let callback: fn(&mut Object, &mut map::Game) = match self {
+        let callback: fn(Entity, &mut map::Game, &mut World) = match self {
             Player => player_death,
             Monster => monster_death,
+            Undead => undead_death,
         };
-        callback(object, game);
+        callback(entity, game, world);
     }
 }
 
@@ -138,61 +287,183 @@ pub struct Fighter {
     pub hp: i32,
     pub defense: i32,
     pub power: i32,
+    pub accuracy: i32,
+    pub evasion: i32,
+    pub damage: combat::Dice,
+    /// experience: for a monster, what it's worth to whoever kills it; for the
+    /// player, how much they've earned so far
+    pub xp: i32,
+    /// how experienced a fighter is; only the player ever goes up a level
+    pub level: i32,
     pub on_death: DeathCallback,
 }
-#[derive(Clone, Debug, PartialEq)]
-pub enum Ai {
-    Basic,
+
+/// move by the given amount, if the destination is not blocked. Walking into
+/// a closed door opens it instead.
+pub fn move_by(entity: Entity, dx: i32, dy: i32, game: &mut map::Game, world: &mut World) {
+    let (x, y) = world.pos(entity);
+    let (x, y) = (x + dx, y + dy);
+    let door = game
+        .map
+        .get(x, y)
+        .map_or(false, |tile| tile.terrain == Terrain::Door);
+    // the player doesn't block, but a monster that hasn't noticed them still
+    // mustn't wander onto their tile
+    let onto_player = entity != world.player && (x, y) == world.pos(world.player);
+    if door {
+        open_door(entity, x, y, game, world);
+    } else if !onto_player && !map::is_blocked_for(entity, x, y, game, world) {
+        let from = game.map[world.pos(entity)].terrain;
+        world.set_pos(entity, x, y);
+        hazards::enter_tile(entity, from, game, world);
+    }
 }
-/// move by the given amount, if the destination is not blocked
-pub fn move_by(id: usize, dx: i32, dy: i32, game: &map::Game, objects: &mut [Object]) {
-    let (x, y) = objects[id].pos();
-    if !map::is_blocked(x + dx, y + dy, &game.map, objects) {
-        objects[id].set_pos(x + dx, y + dy);
+
+fn open_door(entity: Entity, x: i32, y: i32, game: &mut map::Game, world: &World) {
+    game.set_terrain(x, y, Terrain::OpenDoor);
+    if entity == world.player {
+        // the hinges creak loud enough to give the player away
+        game.messages.add("You open the door.", WHITE);
+        game.make_noise(x, y, global::DOOR_NOISE_RADIUS);
     }
 }
 
-pub fn player_move_or_attack(dx: i32, dy: i32, game: &mut map::Game, objects: &mut [Object]) {
+pub fn player_move_or_attack(dx: i32, dy: i32, game: &mut map::Game, world: &mut World) {
     // the coordinates the player is moving to/attacking
-    let x = objects[global::PLAYER].x + dx;
-    let y = objects[global::PLAYER].y + dy;
+    let (x, y) = world.pos(world.player);
+    let (x, y) = (x + dx, y + dy);
 
     // try to find an attackable object there
-    let target_id = objects
+    let target = world
+        .at(x, y)
         .iter()
-        .position(|object| object.fighter.is_some() && object.pos() == (x, y));
+        .copied()
+        .find(|&entity| world.fighters.contains(entity));
 
     // attack if target found, move otherwise
-    match target_id {
-        Some(target_id) => {
-            let (player, target) = utils::mut_two(global::PLAYER, target_id, objects);
-            player.attack(target, game);
-            println!(
-                "The {} laughs at your puny efforts to attack him!",
-                objects[target_id].name
-            );
+    match target {
+        Some(target) => {
+            attack(world.player, target, game, world);
         }
         None => {
-            move_by(global::PLAYER, dx, dy, &game, objects);
+            move_by(world.player, dx, dy, game, world);
         }
     }
 }
 
 pub fn move_towards(
-    id: usize,
+    entity: Entity,
     target_x: i32,
     target_y: i32,
     game: &mut map::Game,
-    objects: &mut [Object],
+    world: &mut World,
 ) {
-    // vector from this object to the target, and distance
-    let dx = target_x - objects[id].x;
-    let dy = target_y - objects[id].y;
+    // follow the cheapest path there, as long as it's not a long way round
+    let (x, y) = world.pos(entity);
+    let path = pathfinding::find_path(
+        game.map.width(),
+        game.map.height(),
+        (x, y),
+        (target_x, target_y),
+        global::MAX_PATH_COST,
+        |tile_x, tile_y| map::path_cost(entity, tile_x, tile_y, game, world),
+    );
+    if let Some(&(next_x, next_y)) = path.as_ref().and_then(|path| path.first()) {
+        move_by(entity, next_x - x, next_y - y, game, world);
+        return;
+    }
+
+    // otherwise just head straight for it: the vector from this object to
+    // the target, and distance
+    let dx = target_x - x;
+    let dy = target_y - y;
     let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
 
     // normalize it to length 1 (preserving direction), then round it and
     // convert to integer so the movement is restricted to the map grid
     let dx = (dx as f32 / distance).round() as i32;
     let dy = (dy as f32 / distance).round() as i32;
-    move_by(id, dx, dy, game, objects);
+    move_by(entity, dx, dy, game, world);
+}
+
+/// step onto the free neighbouring tile farthest from the target.
+/// Returns `false` when no step would increase the distance.
+pub fn move_away_from(
+    entity: Entity,
+    target_x: i32,
+    target_y: i32,
+    game: &mut map::Game,
+    world: &mut World,
+) -> bool {
+    let (x, y) = world.pos(entity);
+    let mut best = (0, 0);
+    let mut best_distance = world.objects[entity].distance_to_pos(target_x, target_y);
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            let distance =
+                (((x + dx - target_x).pow(2) + (y + dy - target_y).pow(2)) as f32).sqrt();
+            if distance > best_distance && !map::is_blocked_for(entity, x + dx, y + dy, game, world)
+            {
+                best = (dx, dy);
+                best_distance = distance;
+            }
+        }
+    }
+    if best == (0, 0) {
+        return false;
+    }
+    let from = game.map[(x, y)].terrain;
+    world.set_pos(entity, x + best.0, y + best.1);
+    hazards::enter_tile(entity, from, game, world);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::Dice;
+
+    #[test]
+    fn monsters_never_step_onto_the_player() {
+        let mut game = map::Game::for_tests(10, 10);
+        let mut world = World::new(10, 10, Object::new(5, 5, '@', "player", WHITE, false));
+        let rat = world.spawn(Object::new(4, 4, 'r', "rat", WHITE, true));
+        move_by(rat, 1, 1, &mut game, &mut world);
+        assert_eq!(world.pos(rat), (4, 4));
+        move_by(rat, 1, 0, &mut game, &mut world);
+        assert_eq!(world.pos(rat), (5, 4));
+    }
+
+    #[test]
+    fn player_dies_only_once() {
+        let mut game = map::Game::for_tests(10, 10);
+        let player = Object::new(5, 5, '@', "player", WHITE, true);
+        let mut world = World::new(10, 10, player);
+        world.fighters.insert(
+            world.player,
+            Fighter {
+                max_hp: 10,
+                hp: 10,
+                defense: 0,
+                power: 0,
+                accuracy: 0,
+                evasion: 0,
+                damage: Dice::new(1, 2),
+                xp: 0,
+                level: 1,
+                on_death: DeathCallback::Player,
+            },
+        );
+        let orc = Cause::Monster("orc".to_string());
+        take_damage(world.player, 20, orc.clone(), &mut game, &mut world);
+        take_damage(world.player, 5, Cause::Burning, &mut game, &mut world);
+
+        assert_eq!(game.death, Some(orc));
+        let deaths = game
+            .messages
+            .iter()
+            .filter(|(message, _)| message == "You died!")
+            .count();
+        assert_eq!(deaths, 1);
+    }
 }
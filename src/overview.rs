@@ -0,0 +1,63 @@
+use crate::map::Game;
+use crate::screen::Layout;
+use crate::world::World;
+use tcod::colors::{self, Color};
+use tcod::console::*;
+
+const LEGEND: &str = "@ you  % remains  ^ trap  * brazier  + door  ~ water  : rubble";
+
+/// How much the player cares about seeing one tile on the overview, and what
+/// to draw for it. Where a cell of the overview covers several tiles, the
+/// most interesting one wins.
+fn overview_cell(x: i32, y: i32, game: &Game, world: &World) -> Option<(i32, char, Color, Color)> {
+    let tile = game.map.get(x, y).filter(|tile| tile.explored)?;
+    let info = tile.memory.terrain.info();
+    let cell = if (x, y) == world.pos(world.player) {
+        (4, '@', colors::WHITE, info.background)
+    } else if let Some((glyph, color)) = tile.memory.object {
+        (3, glyph, color, info.background)
+    } else if info.glyph != ' ' {
+        (2, info.glyph, info.foreground, info.background)
+    } else if !info.blocks {
+        (1, ' ', info.foreground, info.background)
+    } else {
+        (0, ' ', info.foreground, info.background)
+    };
+    Some(cell)
+}
+
+/// Show the whole explored level shrunk down to fit in the window, along with
+/// what the player remembers seeing there, until a key is pressed.
+pub fn show_overview(root: &mut Root, layout: &Layout, game: &Game, world: &World) {
+    // leave the bottom two lines for the legend
+    let width = layout.width;
+    let height = layout.height - 2;
+    // how many tiles each cell stands for, rounded up so the whole level fits
+    let scale_x = (game.map.width() + width - 1) / width;
+    let scale_y = (game.map.height() + height - 1) / height;
+
+    root.set_default_background(colors::BLACK);
+    root.clear();
+    for cell_y in 0..height {
+        for cell_x in 0..width {
+            let best = ((cell_y * scale_y)..((cell_y + 1) * scale_y))
+                .flat_map(|y| ((cell_x * scale_x)..((cell_x + 1) * scale_x)).map(move |x| (x, y)))
+                .filter_map(|(x, y)| overview_cell(x, y, game, world))
+                .max_by_key(|&(priority, ..)| priority);
+            if let Some((_, glyph, foreground, background)) = best {
+                root.put_char_ex(cell_x, cell_y, glyph, foreground, background);
+            }
+        }
+    }
+    root.set_default_foreground(colors::LIGHT_GREY);
+    root.print_ex(1, height, BackgroundFlag::None, TextAlignment::Left, LEGEND);
+    root.print_ex(
+        1,
+        height + 1,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        "Press any key to return.",
+    );
+    root.flush();
+    root.wait_for_keypress(true);
+}
@@ -0,0 +1,201 @@
+use crate::grid::Grid;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Find the cheapest path between two tiles with A*, moving in all eight
+/// directions. `cost` gives what it takes to step onto a tile, or `None` if
+/// it can't be entered at all. The search gives up on paths costing more
+/// than `max_cost`.
+///
+/// Returns the tiles to walk through, starting next to `from` and ending on
+/// `to`, or `None` if there's no way there.
+///
+/// Only the part of the map that a path within `max_cost` could reach is
+/// searched, so a short search stays cheap however big the map is.
+pub fn find_path<F>(
+    width: i32,
+    height: i32,
+    from: (i32, i32),
+    to: (i32, i32),
+    max_cost: i32,
+    cost: F,
+) -> Option<Vec<(i32, i32)>>
+where
+    F: Fn(i32, i32) -> Option<i32>,
+{
+    // every step costs at least 1, so the number of king's moves left is
+    // never more than the real cost
+    let estimate = |(x, y): (i32, i32)| (to.0 - x).abs().max((to.1 - y).abs());
+
+    // for the same reason nothing more than `max_cost` king's moves away can
+    // be reached, so the grids only need to cover that window of the map
+    let left = from.0.saturating_sub(max_cost).max(0);
+    let top = from.1.saturating_sub(max_cost).max(0);
+    let right = from.0.saturating_add(max_cost).min(width - 1);
+    let bottom = from.1.saturating_add(max_cost).min(height - 1);
+    if to.0 < left || to.0 > right || to.1 < top || to.1 > bottom {
+        return None;
+    }
+    let origin = (left, top);
+    let local = |(x, y): (i32, i32)| (x - left, y - top);
+
+    let mut best = Grid::new(right - left + 1, bottom - top + 1, i32::MAX);
+    let mut came_from = Grid::new(best.width(), best.height(), None);
+    let mut open = BinaryHeap::new();
+    best[local(from)] = 0;
+    open.push(Reverse((estimate(from), 0, from)));
+
+    while let Some(Reverse((_, spent, pos))) = open.pop() {
+        if pos == to {
+            return Some(walk_back(&came_from, origin, from, to));
+        }
+        if spent > best[local(pos)] {
+            // already found a cheaper way here
+            continue;
+        }
+        let (x, y) = local(pos);
+        for (next_x, next_y) in best.neighbours(x, y).collect::<Vec<_>>() {
+            let next = (next_x + left, next_y + top);
+            let step = match cost(next.0, next.1) {
+                Some(step) => step,
+                None => continue,
+            };
+            let total = spent + step;
+            if total <= max_cost && total < best[(next_x, next_y)] {
+                best[(next_x, next_y)] = total;
+                came_from[(next_x, next_y)] = Some(pos);
+                open.push(Reverse((total + estimate(next), total, next)));
+            }
+        }
+    }
+    None
+}
+
+/// Find the cheapest path to whichever tile `is_goal` accepts that is nearest
+/// to `from`, with Dijkstra's algorithm. `from` itself doesn't count, even if
+/// it would do. `cost` works the same as for `find_path`.
+pub fn find_nearest<F, G>(
+    width: i32,
+    height: i32,
+    from: (i32, i32),
+    cost: F,
+    is_goal: G,
+) -> Option<Vec<(i32, i32)>>
+where
+    F: Fn(i32, i32) -> Option<i32>,
+    G: Fn(i32, i32) -> bool,
+{
+    let mut best = Grid::new(width, height, i32::MAX);
+    let mut came_from = Grid::new(width, height, None);
+    let mut open = BinaryHeap::new();
+    best[from] = 0;
+    open.push(Reverse((0, from)));
+
+    while let Some(Reverse((spent, pos))) = open.pop() {
+        if pos != from && is_goal(pos.0, pos.1) {
+            return Some(walk_back(&came_from, (0, 0), from, pos));
+        }
+        if spent > best[pos] {
+            continue;
+        }
+        for next in best.neighbours(pos.0, pos.1).collect::<Vec<_>>() {
+            let step = match cost(next.0, next.1) {
+                Some(step) => step,
+                None => continue,
+            };
+            let total = spent + step;
+            if total < best[next] {
+                best[next] = total;
+                came_from[next] = Some(pos);
+                open.push(Reverse((total, next)));
+            }
+        }
+    }
+    None
+}
+
+/// follow the trail of where each tile was reached from, back from the goal
+/// to where we started. `origin` is the map tile the grid's top left corner
+/// stands for
+fn walk_back(
+    came_from: &Grid<Option<(i32, i32)>>,
+    origin: (i32, i32),
+    from: (i32, i32),
+    to: (i32, i32),
+) -> Vec<(i32, i32)> {
+    let mut path = vec![to];
+    let mut current = to;
+    while let Some(previous) = came_from[(current.0 - origin.0, current.1 - origin.1)] {
+        if previous == from {
+            break;
+        }
+        path.push(previous);
+        current = previous;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an open field with a wall down the middle, broken only at the bottom
+    fn wall_cost(x: i32, y: i32) -> Option<i32> {
+        if x == 10 && y < 18 {
+            None
+        } else {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn path_goes_round_a_wall() {
+        let path = find_path(20, 20, (5, 5), (15, 5), 100, wall_cost).unwrap();
+        assert_eq!(path.last(), Some(&(15, 5)));
+        assert!(path.iter().all(|&(x, y)| wall_cost(x, y).is_some()));
+        // each step is to a neighbouring tile
+        let mut previous = (5, 5);
+        for &(x, y) in &path {
+            assert!((x - previous.0).abs() <= 1 && (y - previous.1).abs() <= 1);
+            previous = (x, y);
+        }
+        // down to the gap and back up again
+        assert_eq!(path.len(), 26);
+    }
+
+    #[test]
+    fn path_is_the_same_inside_and_at_the_edge_of_the_window() {
+        // the cheapest way round costs exactly 26, so that's the tightest
+        // window it can be found in
+        let wide = find_path(20, 20, (5, 5), (15, 5), 100, wall_cost);
+        let tight = find_path(20, 20, (5, 5), (15, 5), 26, wall_cost);
+        assert_eq!(wide, tight);
+        assert_eq!(find_path(20, 20, (5, 5), (15, 5), 25, wall_cost), None);
+    }
+
+    #[test]
+    fn too_far_is_given_up_on_straight_away() {
+        let path = find_path(200, 200, (0, 0), (150, 150), 20, |_, _| {
+            panic!("nothing should be searched")
+        });
+        assert_eq!(path, None);
+    }
+
+    #[test]
+    fn window_is_clipped_to_the_map() {
+        // starting in the far corner with a budget bigger than the map
+        let path = find_path(20, 20, (19, 19), (0, 0), i32::MAX, |_, _| Some(1)).unwrap();
+        assert_eq!(path.len(), 19);
+        assert_eq!(path.last(), Some(&(0, 0)));
+    }
+
+    #[test]
+    fn nearest_finds_the_closest_goal() {
+        let path = find_nearest(20, 20, (5, 5), wall_cost, |x, y| {
+            (x, y) == (8, 5) || (x, y) == (1, 5)
+        })
+        .unwrap();
+        assert_eq!(path.last(), Some(&(8, 5)));
+    }
+}
@@ -0,0 +1,46 @@
+use crate::equipment;
+use crate::global;
+use crate::hunger::{self, HungerLevel};
+use crate::objects;
+use crate::world::{Entity, World};
+
+/// Wounds slowly closing up by themselves. The tougher something is, the
+/// faster it heals: `rate` is how much of its maximum hit points comes back
+/// every turn, and `progress` saves up the fractions in between.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Regeneration {
+    pub rate: f32,
+    pub progress: f32,
+}
+
+impl Regeneration {
+    pub fn new(rate: f32) -> Self {
+        Regeneration {
+            rate,
+            progress: 0.0,
+        }
+    }
+}
+
+/// whether something is in a state to heal at all: alive, and not so hungry
+/// that its body has nothing left to mend itself with
+pub fn can_regenerate(entity: Entity, world: &World) -> bool {
+    world.is_alive(entity)
+        && world.regeneration.contains(entity)
+        && hunger::hunger_level(entity, world) < HungerLevel::Weak
+}
+
+/// Give everything that regenerates its turn's worth of healing.
+pub fn regenerate(world: &mut World) {
+    for entity in world.regeneration.entities() {
+        if !can_regenerate(entity, world) {
+            continue;
+        }
+        let max_hp = equipment::max_hp(entity, world);
+        let regeneration = &mut world.regeneration[entity];
+        regeneration.progress += (max_hp as f32 * regeneration.rate).max(global::MIN_REGENERATION);
+        let amount = regeneration.progress.floor();
+        regeneration.progress -= amount;
+        objects::heal(entity, amount as i32, world);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::global;
+
+/// Where everything goes on screen, worked out from the size of the window
+/// rather than fixed at compile time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub width: i32,
+    pub height: i32,
+    /// the part of the window showing the map, above the panel
+    pub view_width: i32,
+    pub view_height: i32,
+    pub panel_y: i32,
+    pub msg_x: i32,
+    pub msg_width: i32,
+    pub msg_height: i32,
+}
+
+impl Layout {
+    pub fn new(width: i32, height: i32) -> Self {
+        let width = width.max(global::MIN_SCREEN_WIDTH);
+        let height = height.max(global::MIN_SCREEN_HEIGHT);
+        Layout {
+            width,
+            height,
+            view_width: width,
+            view_height: height - global::PANEL_HEIGHT,
+            panel_y: height - global::PANEL_HEIGHT,
+            msg_x: global::BAR_WIDTH + 2,
+            msg_width: width - global::BAR_WIDTH - 2,
+            msg_height: global::PANEL_HEIGHT - 1,
+        }
+    }
+}
+
+/// The window onto the map: which map tile is drawn in the top left corner of
+/// the view, so maps bigger than the screen can scroll.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Camera {
+    pub fn new(width: i32, height: i32) -> Self {
+        Camera {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
+    /// centre the view on the given tile, but without showing anything past
+    /// the edges of the map (a map smaller than the view stays in the corner)
+    pub fn center_on(&mut self, x: i32, y: i32, map_width: i32, map_height: i32) {
+        self.x = (x - self.width / 2).min(map_width - self.width).max(0);
+        self.y = (y - self.height / 2).min(map_height - self.height).max(0);
+    }
+
+    /// the map tile shown at the given spot of the view
+    pub fn map_pos(&self, screen_x: i32, screen_y: i32) -> (i32, i32) {
+        (screen_x + self.x, screen_y + self.y)
+    }
+
+    /// where a map tile ends up on screen, if it's in view at all
+    pub fn screen_pos(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let (screen_x, screen_y) = (x - self.x, y - self.y);
+        if screen_x < 0 || screen_y < 0 || screen_x >= self.width || screen_y >= self.height {
+            None
+        } else {
+            Some((screen_x, screen_y))
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::global;
+use crate::map::Map;
+use crate::status::{self, Effect};
+use crate::world::{Entity, World};
+use std::collections::HashMap;
+use tcod::map::FovAlgorithm;
+
+/// tcod's field of view algorithms, in the order the debug key cycles them
+const FOV_ALGORITHMS: [FovAlgorithm; 5] = [
+    FovAlgorithm::Basic,
+    FovAlgorithm::Diamond,
+    FovAlgorithm::Shadow,
+    FovAlgorithm::Permissive4,
+    FovAlgorithm::Restrictive,
+];
+
+/// How the player's field of view is worked out. It lives on the game rather
+/// than in constants so that effects can change how far the player sees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FovSettings {
+    pub algorithm: FovAlgorithm,
+    pub light_walls: bool,
+    /// how far the player sees when nothing is wrong with their eyes
+    pub radius: i32,
+}
+
+impl FovSettings {
+    pub fn new() -> Self {
+        FovSettings {
+            algorithm: global::FOV_ALGO,
+            light_walls: global::FOV_LIGHT_WALLS,
+            radius: global::SIGHT_RADIUS,
+        }
+    }
+
+    /// switch to the next of tcod's algorithms, for comparing them
+    pub fn next_algorithm(&mut self) {
+        let current = FOV_ALGORITHMS
+            .iter()
+            .position(|&algorithm| algorithm == self.algorithm);
+        let next = current.map_or(0, |i| (i + 1) % FOV_ALGORITHMS.len());
+        self.algorithm = FOV_ALGORITHMS[next];
+    }
+}
+
+/// how far the player can actually see right now, after any effects on them
+pub fn player_sight_radius(settings: &FovSettings, world: &World) -> i32 {
+    if status::has_effect(world.player, Effect::Blind, world) {
+        global::BLIND_SIGHT_RADIUS
+    } else {
+        settings.radius
+    }
+}
+
+/// Walk a Bresenham line between two tiles and report whether anything
+/// blocks sight in between. The end points themselves never block.
+pub fn line_of_sight(map: &Map, from: (i32, i32), to: (i32, i32)) -> bool {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    while (x, y) != to {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+        if (x, y) != to && map.get(x, y).map_or(true, |tile| tile.block_sight()) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Remembers line-of-sight results towards a single target (the player), so
+/// a crowd of monsters standing around doesn't re-trace the same lines every
+/// turn. The cache empties itself as soon as the target moves.
+pub struct SightCache {
+    target: (i32, i32),
+    lines: HashMap<(i32, i32), bool>,
+}
+
+impl SightCache {
+    pub fn new() -> Self {
+        SightCache {
+            target: (-1, -1),
+            lines: HashMap::new(),
+        }
+    }
+
+    /// forget everything, e.g. after a door opened or closed
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn line_of_sight(&mut self, map: &Map, from: (i32, i32), to: (i32, i32)) -> bool {
+        if to != self.target {
+            self.target = to;
+            self.lines.clear();
+        }
+        *self
+            .lines
+            .entry(from)
+            .or_insert_with(|| line_of_sight(map, from, to))
+    }
+}
+
+/// How far something can see, in tiles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vision {
+    pub radius: i32,
+}
+
+/// How hard something is to spot: shortens the sight radius of whoever is
+/// looking for it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stealth {
+    pub level: i32,
+}
+
+/// Whether `viewer` can spot `target`: it has to be within the viewer's sight
+/// radius (shortened by the target's stealth) with nothing in the way.
+pub fn can_see(
+    viewer: Entity,
+    target: Entity,
+    world: &World,
+    map: &Map,
+    cache: &mut SightCache,
+) -> bool {
+    if status::has_effect(viewer, Effect::Blind, world) {
+        return false;
+    }
+    let sight_radius = world.vision.get(viewer).map_or(0, |v| v.radius);
+    let stealth = world.stealth.get(target).map_or(0, |s| s.level);
+    let radius = sight_radius - stealth;
+    radius > 0
+        && world.distance(viewer, target) <= radius as f32
+        && cache.line_of_sight(map, world.pos(viewer), world.pos(target))
+}
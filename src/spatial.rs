@@ -0,0 +1,124 @@
+use crate::world::Entity;
+
+/// Which entities stand on which tile, so that tile queries don't have to
+/// scan through every entity in the game.
+pub struct SpatialIndex {
+    width: i32,
+    height: i32,
+    cells: Vec<Vec<Entity>>,
+}
+
+impl SpatialIndex {
+    pub fn new(width: i32, height: i32) -> Self {
+        SpatialIndex {
+            width,
+            height,
+            cells: vec![vec![]; (width * height) as usize],
+        }
+    }
+
+    fn cell(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            None
+        } else {
+            Some((y * self.width + x) as usize)
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, x: i32, y: i32) {
+        if let Some(cell) = self.cell(x, y) {
+            self.cells[cell].push(entity);
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity, x: i32, y: i32) {
+        if let Some(cell) = self.cell(x, y) {
+            self.cells[cell].retain(|&other| other != entity);
+        }
+    }
+
+    pub fn move_object(&mut self, entity: Entity, from: (i32, i32), to: (i32, i32)) {
+        self.remove(entity, from.0, from.1);
+        self.insert(entity, to.0, to.1);
+    }
+
+    /// the entities at the given tile
+    pub fn at(&self, x: i32, y: i32) -> &[Entity] {
+        match self.cell(x, y) {
+            Some(cell) => &self.cells[cell],
+            None => &[],
+        }
+    }
+
+    /// how many entities are indexed, over all tiles
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.cells.iter().map(|cell| cell.len()).sum()
+    }
+
+    /// all entities no further than `radius` tiles away from the given tile
+    pub fn within_radius(&self, x: i32, y: i32, radius: f32) -> Vec<Entity> {
+        let reach = radius.floor() as i32;
+        let mut found = vec![];
+        for tile_y in (y - reach)..=(y + reach) {
+            for tile_x in (x - reach)..=(x + reach) {
+                let distance = (((tile_x - x).pow(2) + (tile_y - y).pow(2)) as f32).sqrt();
+                if distance <= radius {
+                    found.extend_from_slice(self.at(tile_x, tile_y));
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Object;
+    use crate::world::World;
+    use tcod::colors;
+
+    /// a few distinct entities to put in an index of our own
+    fn entities(count: usize) -> Vec<Entity> {
+        let mut world = World::new(1, 1, Object::new(0, 0, '@', "player", colors::WHITE, true));
+        (0..count)
+            .map(|_| world.spawn(Object::new(0, 0, 'o', "orc", colors::WHITE, true)))
+            .collect()
+    }
+
+    #[test]
+    fn move_object_leaves_nothing_behind() {
+        let e = entities(2);
+        let mut index = SpatialIndex::new(5, 5);
+        index.insert(e[0], 1, 1);
+        index.insert(e[1], 1, 1);
+        index.move_object(e[0], (1, 1), (3, 4));
+        assert_eq!(index.at(1, 1), &[e[1]]);
+        assert_eq!(index.at(3, 4), &[e[0]]);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn out_of_bounds_tiles_are_empty() {
+        let e = entities(1);
+        let mut index = SpatialIndex::new(5, 5);
+        index.insert(e[0], 7, -1);
+        assert!(index.at(7, -1).is_empty());
+        assert!(index.at(-1, 0).is_empty());
+        assert_eq!(index.len(), 0);
+    }
+
+    #[test]
+    fn within_radius_is_round() {
+        let e = entities(3);
+        let mut index = SpatialIndex::new(10, 10);
+        index.insert(e[0], 5, 5);
+        index.insert(e[1], 5, 8);
+        // a corner of the square around (5, 5), but further than 3 tiles away
+        index.insert(e[2], 8, 8);
+        let mut found = index.within_radius(5, 5, 3.0);
+        found.sort();
+        assert_eq!(found, vec![e[0], e[1]]);
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+/// Everything counted over the course of a run, for the character screen and
+/// the morgue file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// how many of each kind of monster the player has killed, by name
+    pub kills: BTreeMap<String, i32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub potions_used: i32,
+    pub tiles_explored: i32,
+    /// turns spent on each level, starting with level 1
+    pub turns_per_level: Vec<i32>,
+    pub deepest_level: i32,
+}
+
+impl Stats {
+    pub fn total_kills(&self) -> i32 {
+        self.kills.values().sum()
+    }
+
+    pub fn record_kill(&mut self, name: &str) {
+        *self.kills.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    /// count a turn spent on the given level
+    pub fn record_turn(&mut self, depth: i32) {
+        let level = (depth - 1).max(0) as usize;
+        if self.turns_per_level.len() <= level {
+            self.turns_per_level.resize(level + 1, 0);
+        }
+        self.turns_per_level[level] += 1;
+        self.deepest_level = self.deepest_level.max(depth);
+    }
+
+    /// the statistics written out one per line, for the screens and files
+    /// that show them
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Deepest level: {}", self.deepest_level),
+            format!("Tiles explored: {}", self.tiles_explored),
+            format!("Damage dealt: {}", self.damage_dealt),
+            format!("Damage taken: {}", self.damage_taken),
+            format!("Potions used: {}", self.potions_used),
+        ];
+        for (index, turns) in self.turns_per_level.iter().enumerate() {
+            lines.push(format!("Turns on level {}: {}", index + 1, turns));
+        }
+        lines.push(format!("Monsters killed: {}", self.total_kills()));
+        for (name, count) in &self.kills {
+            lines.push(format!("  {} x {}", count, name));
+        }
+        lines
+    }
+}
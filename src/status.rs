@@ -0,0 +1,239 @@
+use crate::death::Cause;
+use crate::global;
+use crate::map;
+use crate::objects;
+use crate::world::{Entity, World};
+use tcod::colors::*;
+
+/// Something temporarily affecting an entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// takes damage every turn until it runs out or is put out
+    Burning,
+    /// can barely see past the end of its nose
+    Blind,
+    /// senses the minds of monsters nearby, even through walls
+    Telepathic,
+    /// loses every other turn
+    Slow,
+    /// shines brightly, and sees much further by its own light
+    Radiant,
+}
+
+impl Effect {
+    pub fn name(self) -> &'static str {
+        match self {
+            Effect::Burning => "burning",
+            Effect::Blind => "blind",
+            Effect::Telepathic => "telepathic",
+            Effect::Slow => "slowed",
+            Effect::Radiant => "radiant",
+        }
+    }
+}
+
+/// The effects currently on an entity, with how many turns each has left.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statuses {
+    effects: Vec<(Effect, i32)>,
+}
+
+impl Statuses {
+    /// add an effect, or make it last longer if it's already there
+    pub fn add(&mut self, effect: Effect, turns: i32) {
+        match self.effects.iter_mut().find(|(e, _)| *e == effect) {
+            Some((_, turns_left)) => *turns_left = (*turns_left).max(turns),
+            None => self.effects.push((effect, turns)),
+        }
+    }
+
+    /// take an effect off, returning whether it was there at all
+    pub fn remove(&mut self, effect: Effect) -> bool {
+        let count = self.effects.len();
+        self.effects.retain(|&(e, _)| e != effect);
+        self.effects.len() != count
+    }
+
+    pub fn has(&self, effect: Effect) -> bool {
+        self.effects.iter().any(|&(e, _)| e == effect)
+    }
+
+    /// every effect, with how many turns it has left
+    pub fn iter(&self) -> impl Iterator<Item = (Effect, i32)> + '_ {
+        self.effects.iter().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+/// Turns an entity still owes after moving through something slow, like deep
+/// water or rubble. It sits out that many turns before acting again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Delay {
+    pub turns: i32,
+}
+
+pub fn add_effect(entity: Entity, effect: Effect, turns: i32, world: &mut World) {
+    match world.statuses.get_mut(entity) {
+        Some(statuses) => statuses.add(effect, turns),
+        None => {
+            let mut statuses = Statuses::default();
+            statuses.add(effect, turns);
+            world.statuses.insert(entity, statuses);
+        }
+    }
+}
+
+pub fn remove_effect(entity: Entity, effect: Effect, world: &mut World) -> bool {
+    world
+        .statuses
+        .get_mut(entity)
+        .map_or(false, |statuses| statuses.remove(effect))
+}
+
+pub fn has_effect(entity: Entity, effect: Effect, world: &World) -> bool {
+    world
+        .statuses
+        .get(entity)
+        .map_or(false, |statuses| statuses.has(effect))
+}
+
+/// make the entity lose the given number of turns, on top of any it already owes
+pub fn delay(entity: Entity, turns: i32, world: &mut World) {
+    if turns > 0 {
+        let owed = world.delays.get(entity).map_or(0, |d| d.turns);
+        world.delays.insert(
+            entity,
+            Delay {
+                turns: owed + turns,
+            },
+        );
+    }
+}
+
+/// Spend one turn of the entity's delay, if it has any. Returns `true` if the
+/// entity has to sit this turn out.
+pub fn spend_delay(entity: Entity, world: &mut World) -> bool {
+    match world.delays.get_mut(entity) {
+        Some(delay) if delay.turns > 0 => {
+            delay.turns -= 1;
+            true
+        }
+        _ => {
+            world.delays.remove(entity);
+            false
+        }
+    }
+}
+
+/// Apply every effect for one turn and count down how long they have left.
+pub fn tick_statuses(game: &mut map::Game, world: &mut World) {
+    for entity in world.statuses.entities() {
+        if has_effect(entity, Effect::Burning, world) {
+            game.messages.add(
+                format!(
+                    "{} burns for {} hit points.",
+                    world.name(entity),
+                    global::BURN_DAMAGE
+                ),
+                ORANGE,
+            );
+            objects::take_damage(entity, global::BURN_DAMAGE, Cause::Burning, game, world);
+        }
+        // a slowed entity sits out every other turn
+        if has_effect(entity, Effect::Slow, world) && game.turn % 2 == 0 {
+            delay(entity, 1, world);
+        }
+        // it may have burned to death
+        let statuses = match world.statuses.get_mut(entity) {
+            Some(statuses) => statuses,
+            None => continue,
+        };
+        let mut expired = vec![];
+        for (effect, turns_left) in statuses.effects.iter_mut() {
+            *turns_left -= 1;
+            if *turns_left <= 0 {
+                expired.push(*effect);
+            }
+        }
+        statuses.effects.retain(|&(_, turns_left)| turns_left > 0);
+        if statuses.is_empty() {
+            world.statuses.remove(entity);
+        }
+        for effect in expired {
+            match effect {
+                Effect::Burning => game.messages.add(
+                    format!("The flames on {} die down.", world.name(entity)),
+                    WHITE,
+                ),
+                Effect::Blind => game
+                    .messages
+                    .add(format!("{} can see again.", world.name(entity)), WHITE),
+                Effect::Telepathic => game.messages.add(
+                    format!("The minds around {} fade away.", world.name(entity)),
+                    WHITE,
+                ),
+                Effect::Slow => game
+                    .messages
+                    .add(format!("{} speeds up again.", world.name(entity)), WHITE),
+                Effect::Radiant => {
+                    game.messages.add(
+                        format!("The light around {} fades.", world.name(entity)),
+                        WHITE,
+                    );
+                    if entity == world.player {
+                        game.fov.radius = global::SIGHT_RADIUS;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::Dice;
+    use crate::objects::{DeathCallback, Fighter, Object};
+
+    #[test]
+    fn burning_stops_with_the_death() {
+        let mut game = map::Game::for_tests(10, 10);
+        let mut world = World::new(10, 10, Object::new(1, 1, '@', "player", WHITE, true));
+        let orc = world.spawn(Object::new(5, 5, 'o', "orc", WHITE, true));
+        world.fighters.insert(
+            orc,
+            Fighter {
+                max_hp: 10,
+                hp: global::BURN_DAMAGE * 2,
+                defense: 0,
+                power: 0,
+                accuracy: 0,
+                evasion: 0,
+                damage: Dice::new(1, 2),
+                xp: 0,
+                level: 1,
+                on_death: DeathCallback::Monster,
+            },
+        );
+        add_effect(orc, Effect::Burning, 10, &mut world);
+        add_effect(orc, Effect::Slow, 10, &mut world);
+
+        for turn in 0..6 {
+            game.turn = turn;
+            tick_statuses(&mut game, &mut world);
+        }
+
+        let burns = game
+            .messages
+            .iter()
+            .filter(|(message, _)| message.contains("burns for"))
+            .count();
+        assert_eq!(burns, 2);
+        assert!(world.corpses.contains(orc));
+        assert!(!world.statuses.contains(orc));
+        assert!(!world.delays.contains(orc));
+    }
+}
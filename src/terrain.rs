@@ -0,0 +1,122 @@
+use crate::global;
+use tcod::colors::{self, Color};
+
+/// What a tile of the map is made of. Everything about how a kind of terrain
+/// looks and behaves lives in `TERRAIN_TABLE`, not in the tiles themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Terrain {
+    Floor,
+    Wall,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Door,
+    OpenDoor,
+    Rubble,
+}
+
+/// The properties shared by every tile of one kind of terrain.
+#[derive(Debug)]
+pub struct TerrainInfo {
+    pub name: &'static str,
+    /// drawn on top of the background; ' ' for nothing
+    pub glyph: char,
+    pub foreground: Color,
+    pub background: Color,
+    pub blocks: bool,
+    pub blocks_sight: bool,
+    /// turns it takes to step onto it
+    pub move_cost: i32,
+    /// monsters know better than to walk onto it
+    pub hazardous: bool,
+}
+
+/// indexed by `Terrain as usize`, so keep it in the same order as the enum
+static TERRAIN_TABLE: [TerrainInfo; 8] = [
+    TerrainInfo {
+        name: "floor",
+        glyph: ' ',
+        foreground: colors::WHITE,
+        background: global::COLOR_DARK_GROUND,
+        blocks: false,
+        blocks_sight: false,
+        move_cost: 1,
+        hazardous: false,
+    },
+    TerrainInfo {
+        name: "wall",
+        glyph: ' ',
+        foreground: colors::WHITE,
+        background: global::COLOR_DARK_WALL,
+        blocks: true,
+        blocks_sight: true,
+        move_cost: 1,
+        hazardous: false,
+    },
+    TerrainInfo {
+        name: "shallow water",
+        glyph: '~',
+        foreground: colors::LIGHT_BLUE,
+        background: global::COLOR_SHALLOW_WATER,
+        blocks: false,
+        blocks_sight: false,
+        move_cost: 2,
+        hazardous: false,
+    },
+    TerrainInfo {
+        name: "deep water",
+        glyph: '~',
+        foreground: colors::LIGHT_BLUE,
+        background: global::COLOR_DEEP_WATER,
+        blocks: false,
+        blocks_sight: false,
+        move_cost: 3,
+        hazardous: false,
+    },
+    TerrainInfo {
+        name: "lava",
+        glyph: '~',
+        foreground: colors::YELLOW,
+        background: global::COLOR_LAVA,
+        blocks: false,
+        blocks_sight: false,
+        move_cost: 1,
+        hazardous: true,
+    },
+    TerrainInfo {
+        name: "door",
+        glyph: '+',
+        foreground: global::COLOR_DOOR,
+        background: global::COLOR_DARK_GROUND,
+        blocks: true,
+        blocks_sight: true,
+        move_cost: 2,
+        hazardous: false,
+    },
+    TerrainInfo {
+        name: "open door",
+        glyph: '\'',
+        foreground: global::COLOR_DOOR,
+        background: global::COLOR_DARK_GROUND,
+        blocks: false,
+        blocks_sight: false,
+        move_cost: 1,
+        hazardous: false,
+    },
+    TerrainInfo {
+        name: "rubble",
+        glyph: ':',
+        foreground: global::COLOR_RUBBLE,
+        background: global::COLOR_DARK_GROUND,
+        blocks: false,
+        blocks_sight: false,
+        move_cost: 2,
+        hazardous: false,
+    },
+];
+
+impl Terrain {
+    pub fn info(self) -> &'static TerrainInfo {
+        &TERRAIN_TABLE[self as usize]
+    }
+}
@@ -1,14 +1,57 @@
-use std::cmp;
-
-/// Mutably borrow two *separate* elements from the given slice.
-/// Panics when the indexes are equal or out of bounds.
-pub fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut T, &mut T) {
-    assert!(first_index != second_index);
-    let split_at_index = cmp::max(first_index, second_index);
-    let (first_slice, second_slice) = items.split_at_mut(split_at_index);
-    if first_index < second_index {
-        (&mut first_slice[first_index], &mut second_slice[0])
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The value passed as `--seed <n>` on the command line, if any.
+pub fn seed_from_args() -> Option<usize> {
+    env::args()
+        .skip_while(|arg| arg != "--seed")
+        .nth(1)
+        .and_then(|seed| seed.parse().ok())
+}
+
+/// The window size passed as `--size <width>x<height>` on the command line,
+/// if any.
+pub fn size_from_args() -> Option<(i32, i32)> {
+    let size = env::args().skip_while(|arg| arg != "--size").nth(1)?;
+    let (width, height) = size.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// A seed that differs from run to run, taken from the system clock.
+pub fn time_seed() -> usize {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as usize)
+}
+
+/// "a orc" reads wrong: put the right article in front of a name
+pub fn with_article(name: &str) -> String {
+    let vowel = matches!(name.chars().next(), Some(c) if "aeiouAEIOU".contains(c));
+    if vowel {
+        format!("an {}", name)
     } else {
-        (&mut second_slice[0], &mut first_slice[second_index])
+        format!("a {}", name)
     }
 }
+
+/// Today's date (in UTC) as YYYY-MM-DD, worked out from the system clock.
+pub fn today() -> String {
+    let days = (time_seed() / 86_400) as i64;
+    // days since 1970-01-01 to a calendar date, counting in 400-year eras
+    // that start on the 1st of March, so leap days fall at the end of a year
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,417 @@
+use crate::ai::{Ai, Awareness};
+use crate::corpses::Corpse;
+use crate::equipment::Equipment;
+use crate::hazards::Trap;
+use crate::hunger::Hunger;
+use crate::items::Item;
+use crate::lighting::Light;
+use crate::magic::{Mana, Spellbook};
+use crate::objects::{Fighter, Object};
+use crate::regeneration::Regeneration;
+use crate::sight::{Stealth, Vision};
+use crate::spatial::SpatialIndex;
+use crate::status::{Delay, Statuses};
+use std::collections::BTreeMap;
+use std::ops::{Index, IndexMut};
+
+/// A stable handle to something in the world. Unlike a position in a list it
+/// stays valid while other entities come and go, and is never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entity(u32);
+
+/// One kind of component, for every entity that has it. Kept in id order so
+/// that iterating (and therefore every RNG roll made while iterating) is the
+/// same from run to run.
+#[derive(Debug)]
+pub struct Storage<T> {
+    components: BTreeMap<Entity, T>,
+}
+
+impl<T> Storage<T> {
+    pub fn new() -> Self {
+        Storage {
+            components: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, component: T) {
+        self.components.insert(entity, component);
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.components.remove(&entity)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.components.get(&entity)
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.components.get_mut(&entity)
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.components.contains_key(&entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.components
+            .iter()
+            .map(|(&entity, component)| (entity, component))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.components
+            .iter_mut()
+            .map(|(&entity, component)| (entity, component))
+    }
+
+    /// a snapshot of the entities that have this component, for when the
+    /// loop body needs to change the world
+    pub fn entities(&self) -> Vec<Entity> {
+        self.components.keys().copied().collect()
+    }
+}
+
+impl<T> Index<Entity> for Storage<T> {
+    type Output = T;
+
+    fn index(&self, entity: Entity) -> &T {
+        &self.components[&entity]
+    }
+}
+
+impl<T> IndexMut<Entity> for Storage<T> {
+    fn index_mut(&mut self, entity: Entity) -> &mut T {
+        self.components
+            .get_mut(&entity)
+            .expect("entity has no such component")
+    }
+}
+
+/// Everything that exists on the current level. Every entity has an `Object`
+/// (where it is and how it looks); everything else is an optional component
+/// in its own storage, so new kinds of behaviour don't have to grow `Object`.
+pub struct World {
+    next_id: u32,
+    pub player: Entity,
+    pub objects: Storage<Object>,
+    pub fighters: Storage<Fighter>,
+    pub ais: Storage<Ai>,
+    pub awareness: Storage<Awareness>,
+    pub vision: Storage<Vision>,
+    pub stealth: Storage<Stealth>,
+    pub corpses: Storage<Corpse>,
+    pub statuses: Storage<Statuses>,
+    pub delays: Storage<Delay>,
+    pub traps: Storage<Trap>,
+    pub lights: Storage<Light>,
+    pub hunger: Storage<Hunger>,
+    pub items: Storage<Item>,
+    pub regeneration: Storage<Regeneration>,
+    pub equipment: Storage<Equipment>,
+    pub mana: Storage<Mana>,
+    pub spellbooks: Storage<Spellbook>,
+    /// what the player is carrying; these are off the map, so the spatial
+    /// index doesn't know about them
+    pub inventory: Vec<Entity>,
+    index: SpatialIndex,
+}
+
+impl World {
+    /// an empty world of the given size, apart from the player
+    pub fn new(width: i32, height: i32, player: Object) -> Self {
+        let mut world = World {
+            next_id: 0,
+            player: Entity(0),
+            objects: Storage::new(),
+            fighters: Storage::new(),
+            ais: Storage::new(),
+            awareness: Storage::new(),
+            vision: Storage::new(),
+            stealth: Storage::new(),
+            corpses: Storage::new(),
+            statuses: Storage::new(),
+            delays: Storage::new(),
+            traps: Storage::new(),
+            lights: Storage::new(),
+            hunger: Storage::new(),
+            items: Storage::new(),
+            regeneration: Storage::new(),
+            equipment: Storage::new(),
+            mana: Storage::new(),
+            spellbooks: Storage::new(),
+            inventory: vec![],
+            index: SpatialIndex::new(width, height),
+        };
+        world.player = world.spawn(player);
+        world
+    }
+
+    /// add a new entity at the object's position
+    pub fn spawn(&mut self, object: Object) -> Entity {
+        let entity = Entity(self.next_id);
+        self.next_id += 1;
+        self.index.insert(entity, object.x, object.y);
+        self.objects.insert(entity, object);
+        entity
+    }
+
+    /// remove an entity and all of its components for good
+    pub fn despawn(&mut self, entity: Entity) {
+        if let Some(object) = self.objects.remove(entity) {
+            self.index.remove(entity, object.x, object.y);
+        }
+        self.inventory.retain(|&item| item != entity);
+        self.fighters.remove(entity);
+        self.ais.remove(entity);
+        self.awareness.remove(entity);
+        self.vision.remove(entity);
+        self.stealth.remove(entity);
+        self.corpses.remove(entity);
+        self.statuses.remove(entity);
+        self.delays.remove(entity);
+        self.traps.remove(entity);
+        self.lights.remove(entity);
+        self.hunger.remove(entity);
+        self.items.remove(entity);
+        self.regeneration.remove(entity);
+        self.equipment.remove(entity);
+        self.mana.remove(entity);
+        self.spellbooks.remove(entity);
+    }
+
+    /// take an entity off the map and into the player's inventory
+    pub fn pick_up(&mut self, entity: Entity) {
+        let (x, y) = self.pos(entity);
+        self.index.remove(entity, x, y);
+        self.inventory.push(entity);
+    }
+
+    /// take an entity out of the player's inventory and put it on the map
+    pub fn put_down(&mut self, entity: Entity, x: i32, y: i32) {
+        self.inventory.retain(|&item| item != entity);
+        let object = &mut self.objects[entity];
+        object.x = x;
+        object.y = y;
+        self.index.insert(entity, x, y);
+    }
+
+    /// put an entity on a new tile, keeping the spatial index up to date
+    pub fn set_pos(&mut self, entity: Entity, x: i32, y: i32) {
+        let object = &mut self.objects[entity];
+        self.index.move_object(entity, (object.x, object.y), (x, y));
+        object.x = x;
+        object.y = y;
+    }
+
+    pub fn pos(&self, entity: Entity) -> (i32, i32) {
+        self.objects[entity].pos()
+    }
+
+    pub fn name(&self, entity: Entity) -> &str {
+        &self.objects[entity].name
+    }
+
+    /// the entities on the given tile
+    pub fn at(&self, x: i32, y: i32) -> &[Entity] {
+        self.index.at(x, y)
+    }
+
+    /// the entity blocking the given tile, if there is one
+    pub fn blocking_at(&self, x: i32, y: i32) -> Option<Entity> {
+        self.index
+            .at(x, y)
+            .iter()
+            .copied()
+            .find(|&entity| self.objects[entity].blocks)
+    }
+
+    /// all entities no further than `radius` tiles away from the given tile
+    pub fn within_radius(&self, x: i32, y: i32, radius: f32) -> Vec<Entity> {
+        self.index.within_radius(x, y, radius)
+    }
+
+    pub fn distance(&self, a: Entity, b: Entity) -> f32 {
+        self.objects[a].distance_to(&self.objects[b])
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.fighters.get(entity).map_or(false, |f| f.hp > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tcod::colors;
+
+    fn object(x: i32, y: i32, blocks: bool) -> Object {
+        Object::new(x, y, 'o', "orc", colors::WHITE, blocks)
+    }
+
+    /// every entity on the map is indexed at its own position and nowhere else
+    fn assert_in_sync(world: &World) {
+        for (entity, object) in world.objects.iter() {
+            let carried = world.inventory.contains(&entity);
+            assert_eq!(
+                world.at(object.x, object.y).contains(&entity),
+                !carried,
+                "{:?} at ({}, {})",
+                entity,
+                object.x,
+                object.y
+            );
+        }
+        let indexed = world.index.len();
+        assert_eq!(
+            indexed,
+            world.objects.iter().count() - world.inventory.len()
+        );
+    }
+
+    #[test]
+    fn set_pos_moves_the_entity_in_the_index() {
+        let mut world = World::new(10, 10, object(1, 1, true));
+        let orc = world.spawn(object(2, 2, true));
+        world.set_pos(orc, 5, 6);
+        assert!(world.at(2, 2).is_empty());
+        assert_eq!(world.at(5, 6), &[orc]);
+        assert_eq!(world.blocking_at(5, 6), Some(orc));
+        assert_in_sync(&world);
+    }
+
+    #[test]
+    fn pick_up_and_put_down_take_the_item_off_and_back_on_the_map() {
+        let mut world = World::new(10, 10, object(1, 1, true));
+        let item = world.spawn(object(3, 3, false));
+        world.pick_up(item);
+        assert!(world.at(3, 3).is_empty());
+        assert_eq!(world.inventory, vec![item]);
+        assert_in_sync(&world);
+
+        world.put_down(item, 7, 8);
+        assert_eq!(world.at(7, 8), &[item]);
+        assert!(world.inventory.is_empty());
+        assert_eq!(world.pos(item), (7, 8));
+        assert_in_sync(&world);
+    }
+
+    #[test]
+    fn despawn_removes_the_entity_from_the_index() {
+        let mut world = World::new(10, 10, object(1, 1, true));
+        let orc = world.spawn(object(4, 4, true));
+        let item = world.spawn(object(4, 4, false));
+        world.despawn(orc);
+        assert_eq!(world.at(4, 4), &[item]);
+        assert_eq!(world.blocking_at(4, 4), None);
+        // a carried item is off the map, but still has to go from the inventory
+        world.pick_up(item);
+        world.despawn(item);
+        assert!(world.inventory.is_empty());
+        assert_in_sync(&world);
+    }
+
+    #[test]
+    fn within_radius_matches_a_scan_of_every_object() {
+        let mut world = World::new(30, 30, object(15, 15, true));
+        for i in 0..200 {
+            world.spawn(object(i * 7 % 30, i * 13 % 30, i % 3 == 0));
+        }
+        let mut indexed = world.within_radius(15, 15, 6.5);
+        let mut scanned = scan_within_radius(&all_objects(&world), 15, 15, 6.5);
+        indexed.sort();
+        scanned.sort();
+        assert_eq!(indexed, scanned);
+    }
+
+    /// every object with its entity, the way a plain list of them would hold it
+    fn all_objects(world: &World) -> Vec<(Entity, &Object)> {
+        world
+            .objects
+            .entities()
+            .into_iter()
+            .map(|entity| (entity, &world.objects[entity]))
+            .collect()
+    }
+
+    fn scan_within_radius(
+        objects: &[(Entity, &Object)],
+        x: i32,
+        y: i32,
+        radius: f32,
+    ) -> Vec<Entity> {
+        objects
+            .iter()
+            .filter(|(_, object)| {
+                (((object.x - x).pow(2) + (object.y - y).pow(2)) as f32).sqrt() <= radius
+            })
+            .map(|&(entity, _)| entity)
+            .collect()
+    }
+
+    /// Compare the index against scanning every object, on a big level packed
+    /// with monsters. Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn index_is_faster_than_scanning() {
+        const SIZE: i32 = 200;
+        const QUERIES: i32 = 20_000;
+        let mut world = World::new(SIZE, SIZE, object(0, 0, true));
+        for i in 0..5_000 {
+            world.spawn(object(i * 37 % SIZE, i * 91 % SIZE, i % 2 == 0));
+        }
+        let objects = all_objects(&world);
+        let query = |i: i32| (i * 17 % SIZE, i * 29 % SIZE);
+
+        let time = |name: &str, run: &dyn Fn() -> usize| {
+            let start = Instant::now();
+            let found = run();
+            println!("{:>24}: {:?} ({} found)", name, start.elapsed(), found);
+            found
+        };
+
+        let indexed = time("at (index)", &|| {
+            (0..QUERIES)
+                .map(|i| world.at(query(i).0, query(i).1).len())
+                .sum()
+        });
+        let scanned = time("at (scan)", &|| {
+            (0..QUERIES)
+                .map(|i| {
+                    let (x, y) = query(i);
+                    objects.iter().filter(|(_, o)| o.pos() == (x, y)).count()
+                })
+                .sum()
+        });
+        assert_eq!(indexed, scanned);
+
+        let indexed = time("blocking_at (index)", &|| {
+            (0..QUERIES)
+                .filter(|&i| world.blocking_at(query(i).0, query(i).1).is_some())
+                .count()
+        });
+        let scanned = time("blocking_at (scan)", &|| {
+            (0..QUERIES)
+                .filter(|&i| {
+                    let (x, y) = query(i);
+                    objects.iter().any(|(_, o)| o.blocks && o.pos() == (x, y))
+                })
+                .count()
+        });
+        assert_eq!(indexed, scanned);
+
+        let indexed = time("within_radius (index)", &|| {
+            (0..QUERIES / 10)
+                .map(|i| world.within_radius(query(i).0, query(i).1, 8.0).len())
+                .sum()
+        });
+        let scanned = time("within_radius (scan)", &|| {
+            (0..QUERIES / 10)
+                .map(|i| scan_within_radius(&objects, query(i).0, query(i).1, 8.0).len())
+                .sum()
+        });
+        assert_eq!(indexed, scanned);
+    }
+}